@@ -1,11 +1,74 @@
 extern crate argparse;
 
-use argparse::{ArgumentParser, Store};
+use argparse::{ArgumentParser, Store, StoreOption, StoreTrue};
+
+use std::env;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+use crate::grid::Grid;
+use crate::mask::Mask;
+use crate::minefield::{self, FirstClick, MineDistribution};
+use crate::neighbourhood::Neighbourhood;
+use crate::replay::PlaybackSpeed;
+use crate::topology::Topology;
+
+/// Classic board sizes, with their exact mine counts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl Difficulty {
+    /// Rows, columns and mines of the preset.
+    pub fn board(&self) -> (usize, usize, usize) {
+        match self {
+            Difficulty::Beginner => (9, 9, 10),
+            Difficulty::Intermediate => (16, 16, 40),
+            Difficulty::Expert => (16, 30, 99),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err(format!("unknown difficulty '{s}'")),
+        }
+    }
+}
 
 pub struct Config {
     pub rows: usize,
     pub cols: usize,
-    pub mines_percent: f32,
+    pub depth: usize,
+    pub mines: usize,
+    pub grid: Grid,
+    pub neighbourhood: Neighbourhood,
+    pub topology: Topology,
+    pub mask: Option<Mask>,
+    pub mine_distribution: MineDistribution,
+    pub first_click: FirstClick,
+    pub practice: bool,
+    pub seed: Option<u64>,
+    pub load: Option<String>,
+    pub layout: Option<String>,
+    pub save_file: String,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub replay_speed: PlaybackSpeed,
+    pub name: String,
+    pub scores_file: String,
+    pub list_scores: bool,
+    pub clear_scores: bool,
     pub tile_width: usize,
     pub tile_height: usize,
     pub tile_gap: usize,
@@ -15,7 +78,31 @@ impl Config {
     pub fn build() -> Config {
         let mut rows: usize = 16;
         let mut cols: usize = 30;
+        let mut depth: usize = 1;
         let mut mines_percent = 20.0;
+        let mut mines = None;
+        let mut difficulty: Option<Difficulty> = None;
+        let mut grid = Grid::Square;
+        let mut neighbourhood = Neighbourhood::Touching;
+        let mut neighbourhood_file: Option<String> = None;
+        let mut topology = Topology::Plane;
+        let mut mask_file: Option<String> = None;
+        let mut double_mines_percent = 0.0;
+        let mut triple_mines_percent = 0.0;
+        let mut anti_mines_percent = 0.0;
+        let mut first_click = FirstClick::Safe;
+        let mut practice = false;
+        let mut seed = None;
+        let mut load = None;
+        let mut layout = None;
+        let mut save_file = "mines.sav".to_string();
+        let mut record = None;
+        let mut replay = None;
+        let mut replay_speed = PlaybackSpeed::Factor(1.0);
+        let mut name = env::var("USER").unwrap_or_else(|_| "player".to_string());
+        let mut scores_file = "mines.scores".to_string();
+        let mut list_scores = false;
+        let mut clear_scores = false;
         let mut tile_width = 30;
         let mut tile_height = 30;
         let mut tile_gap = 2;
@@ -27,11 +114,132 @@ impl Config {
                 .add_option(&["-r", "--rows"], Store, "Number of rows");
             ap.refer(&mut cols)
                 .add_option(&["-c", "--cols"], Store, "Number of columns");
+            ap.refer(&mut depth).add_option(
+                &["--depth"],
+                Store,
+                "Number of layers, more than one makes a 3D board",
+            );
             ap.refer(&mut mines_percent).add_option(
                 &["-p", "--percent"],
                 Store,
                 "Percentage of mines",
             );
+            ap.refer(&mut mines).add_option(
+                &["-m", "--mines"],
+                StoreOption,
+                "Exact number of mines, instead of a percentage",
+            );
+            ap.refer(&mut difficulty).add_option(
+                &["-d", "--difficulty"],
+                StoreOption,
+                "Board preset: beginner, intermediate or expert",
+            );
+            ap.refer(&mut grid).add_option(
+                &["--grid"],
+                Store,
+                "Shape of the tiles: square, hex or triangle",
+            );
+            ap.refer(&mut neighbourhood).add_option(
+                &["--neighbourhood"],
+                Store,
+                "Tiles counted by danger levels: touching, knight, von-neumann, radius-2, \
+                 or 'custom' followed by row,col offsets",
+            );
+            ap.refer(&mut neighbourhood_file).add_option(
+                &["--neighbourhood-file"],
+                StoreOption,
+                "Read the neighbourhood from a grid drawn in a text file",
+            );
+            ap.refer(&mut topology).add_option(
+                &["-t", "--topology"],
+                Store,
+                "Edges of the board: plane, cylinder, torus, mobius or klein",
+            );
+            ap.refer(&mut double_mines_percent).add_option(
+                &["--double-mines"],
+                Store,
+                "Percentage of mined tiles holding two mines",
+            );
+            ap.refer(&mut triple_mines_percent).add_option(
+                &["--triple-mines"],
+                Store,
+                "Percentage of mined tiles holding three mines",
+            );
+            ap.refer(&mut anti_mines_percent).add_option(
+                &["--anti-mines"],
+                Store,
+                "Percentage of mined tiles holding anti-mines, which count -1 in danger levels",
+            );
+            ap.refer(&mut mask_file).add_option(
+                &["--mask"],
+                StoreOption,
+                "Shape the board after a text file or a monochrome image, .pbm or .png",
+            );
+            ap.refer(&mut first_click).add_option(
+                &["-f", "--first-click"],
+                Store,
+                "First uncovered tile: any, safe, opening or no-guess",
+            );
+            ap.refer(&mut practice).add_option(
+                &["--practice"],
+                StoreTrue,
+                "Allow undoing a move that hit a mine",
+            );
+            ap.refer(&mut seed).add_option(
+                &["-s", "--seed"],
+                StoreOption,
+                "Seed of the first board, random if not given",
+            );
+            ap.refer(&mut load).add_option(
+                &["-l", "--load"],
+                StoreOption,
+                "Resume a saved game from a file",
+            );
+            ap.refer(&mut layout).add_option(
+                &["--layout"],
+                StoreOption,
+                "Play a hand-authored board from a text file",
+            );
+            ap.refer(&mut save_file).add_option(
+                &["--save-file"],
+                Store,
+                "File written by the save key",
+            );
+            ap.refer(&mut record).add_option(
+                &["--record"],
+                StoreOption,
+                "Write a replay of the session to a file when quitting",
+            );
+            ap.refer(&mut replay).add_option(
+                &["--replay"],
+                StoreOption,
+                "Play back a replay file instead of playing",
+            );
+            ap.refer(&mut replay_speed).add_option(
+                &["--replay-speed"],
+                Store,
+                "Playback speed factor, or 'step' to advance with space",
+            );
+            ap.refer(&mut name).add_option(
+                &["-n", "--name"],
+                Store,
+                "Player name written in the high scores",
+            );
+            ap.refer(&mut scores_file).add_option(
+                &["--scores-file"],
+                Store,
+                "File keeping the high scores",
+            );
+            ap.refer(&mut list_scores).add_option(
+                &["--scores"],
+                StoreTrue,
+                "Print the high score tables and exit",
+            );
+            ap.refer(&mut clear_scores).add_option(
+                &["--clear-scores"],
+                StoreTrue,
+                "Erase the high score tables and exit",
+            );
             ap.refer(&mut tile_width)
                 .add_option(&["-w", "--tile-width"], Store, "Width of a tile");
             ap.refer(&mut tile_height).add_option(
@@ -47,10 +255,85 @@ impl Config {
             ap.parse_args_or_exit();
         }
 
+        // a preset sets the whole board, only its mine count can be overridden
+        if let Some(difficulty) = difficulty {
+            let (preset_rows, preset_cols, preset_mines) = difficulty.board();
+            rows = preset_rows;
+            cols = preset_cols;
+            mines = mines.or(Some(preset_mines));
+        }
+
+        if let Some(path) = neighbourhood_file {
+            neighbourhood = Neighbourhood::load(Path::new(&path)).unwrap_or_else(|e| {
+                eprintln!("Invalid neighbourhood: {e}");
+                process::exit(2);
+            });
+        }
+
+        // the mask decides the size of the board
+        let mask = mask_file.map(|path| {
+            Mask::load(Path::new(&path)).unwrap_or_else(|e| {
+                eprintln!("Invalid mask: {e}");
+                process::exit(2);
+            })
+        });
+        if let Some(mask) = &mask {
+            rows = mask.rows();
+            cols = mask.cols();
+        }
+
+        if !(0.0..=100.0).contains(&mines_percent) {
+            eprintln!("Invalid board: mine percentage must be between 0 and 100");
+            process::exit(2);
+        }
+        let cells = mask.as_ref().map_or(rows * cols, |mask| mask.cell_count());
+        let mines = mines
+            .unwrap_or_else(|| minefield::mine_count_from_percent(cells * depth, mines_percent));
+        let mine_distribution = MineDistribution {
+            double: double_mines_percent / 100.0,
+            triple: triple_mines_percent / 100.0,
+            anti: anti_mines_percent / 100.0,
+        };
+        if let Err(e) = minefield::validate_board(rows, cols, depth, mask.as_ref(), mines)
+            .and_then(|_| grid.validate_depth(depth))
+            .and_then(|_| grid.validate_neighbourhood(&neighbourhood))
+            .and_then(|_| grid.validate_topology(topology, rows, cols))
+            .and_then(|_| mine_distribution.validate())
+            .and_then(|_| {
+                // the solver cannot prove anything once mines cancel out
+                if first_click == FirstClick::NoGuess && mine_distribution.has_anti_mines() {
+                    return Err("no-guess boards cannot have anti-mines".to_string());
+                }
+                Ok(())
+            })
+        {
+            eprintln!("Invalid board: {e}");
+            process::exit(2);
+        }
+
         Config {
             rows,
             cols,
-            mines_percent,
+            depth,
+            mines,
+            grid,
+            neighbourhood,
+            topology,
+            mask,
+            mine_distribution,
+            first_click,
+            practice,
+            seed,
+            load,
+            layout,
+            save_file,
+            record,
+            replay,
+            replay_speed,
+            name,
+            scores_file,
+            list_scores,
+            clear_scores,
             tile_width,
             tile_height,
             tile_gap,
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+
+use crate::minefield::{Flag, LossMark, Minefield, TileContent};
+
+/// Draws the board as text, one line per row and layers separated by a blank line:
+///
+/// - `#` hidden tile, `F` mine flag, `D` and `T` double and triple mine flags,
+///   `?` question flag
+/// - `.` uncovered zero, `1` to `9` then `a` to `z` for danger levels, `*` mine,
+///   `&` and `%` double and triple mines
+/// - on a lost game `!` is the exploded mine and `X` a wrong flag
+/// - a space is a hole of the mask
+///
+/// On boards with anti-mines every tile takes two characters, the first one a
+/// `-` for anti-mines, their flags and negative danger levels.
+pub fn to_text(minefield: &Minefield) -> String {
+    let mut out = String::new();
+    let signed = minefield.mine_distribution().has_anti_mines();
+
+    for layer in 0..minefield.depth() {
+        if layer > 0 {
+            out.push('\n');
+        }
+        for row in 0..minefield.rows() {
+            for col in 0..minefield.cols() {
+                let i = minefield.position_to_tile(layer, row, col);
+                let (negative, c) = if minefield.tile_exists(i) {
+                    tile_char(minefield, i)
+                } else {
+                    (false, ' ')
+                };
+                if signed {
+                    out.push(if negative { '-' } else { ' ' });
+                }
+                out.push(c);
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+pub fn write_text(minefield: &Minefield, path: &Path) -> Result<(), String> {
+    fs::write(path, to_text(minefield)).map_err(|e| format!("cannot write {}: {e}", path.display()))
+}
+
+/// Character of a tile, and whether it shows something negative.
+fn tile_char(minefield: &Minefield, tile_number: usize) -> (bool, char) {
+    let content = minefield.get_tile_content(tile_number);
+    let negative = content.charge() < 0;
+    match minefield.get_loss_mark(tile_number) {
+        Some(LossMark::Exploded) => return (negative, '!'),
+        Some(LossMark::WrongFlag) => return (false, 'X'),
+        Some(LossMark::MissedMine) => {
+            return (negative, mine_char(minefield.mines_on_tile(tile_number)))
+        }
+        None => {}
+    }
+
+    let flag = minefield.get_tile_flag(tile_number);
+    let flagged_mine = matches!(flag, Some(Flag::Mine(_) | Flag::AntiMine(_)));
+    if minefield.tile_is_hidden(tile_number) || flagged_mine {
+        return match flag {
+            Some(Flag::Mine(mines)) => (false, flag_char(mines)),
+            Some(Flag::AntiMine(mines)) => (true, flag_char(mines)),
+            Some(Flag::Question) => (false, '?'),
+            None => (false, '#'),
+        };
+    }
+
+    match content {
+        TileContent::Mine(mines) | TileContent::AntiMine(mines) => (negative, mine_char(mines)),
+        TileContent::Danger(0) => (false, '.'),
+        TileContent::Danger(danger_level) => (
+            danger_level < 0,
+            char::from_digit(danger_level.unsigned_abs(), 36).unwrap_or('+'),
+        ),
+    }
+}
+
+fn flag_char(mines: u8) -> char {
+    match mines {
+        1 => 'F',
+        2 => 'D',
+        _ => 'T',
+    }
+}
+
+fn mine_char(mines: u8) -> char {
+    match mines {
+        1 => '*',
+        2 => '&',
+        _ => '%',
+    }
+}
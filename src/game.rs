@@ -0,0 +1,417 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::rect::{Point, Rect};
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::export;
+use crate::grid::Grid;
+use crate::minefield::{GameState, MineDistribution, Minefield};
+use crate::neighbourhood::Neighbourhood;
+use crate::renderer::{board_layout, export_png, Heatmap, MinefieldRenderer};
+use crate::replay::{Move, Player, Recorder, Replay};
+use crate::scores::{HighScores, Score};
+use crate::solver;
+use crate::stats::Stats;
+use crate::status_bar::StatusBarRenderer;
+use crate::topology::Topology;
+
+pub fn run(config: Config) -> Result<(), String> {
+    let mut player = None;
+    let mut minefield = match (&config.replay, &config.load, &config.layout) {
+        (Some(path), _, _) => {
+            let replay = Replay::load(Path::new(path))?;
+            let minefield = replay.board()?;
+            player = Some(Player::new(replay, config.replay_speed));
+            minefield
+        }
+        (None, Some(path), _) => Minefield::load(Path::new(path))?,
+        (None, None, Some(path)) => {
+            let mut minefield = Minefield::load_layout(Path::new(path))?;
+            config
+                .grid
+                .validate_topology(config.topology, minefield.rows(), minefield.cols())?;
+            minefield.set_grid(config.grid);
+            minefield.set_neighbourhood(config.neighbourhood.clone());
+            minefield.set_topology(config.topology);
+            minefield
+        }
+        (None, None, None) => {
+            let mut minefield = Minefield::with_depth(
+                config.rows,
+                config.cols,
+                config.depth,
+                config.mines,
+                config.first_click,
+                config.seed.unwrap_or_else(rand::random),
+            );
+            minefield.set_grid(config.grid);
+            minefield.set_neighbourhood(config.neighbourhood.clone());
+            minefield.set_topology(config.topology);
+            minefield.set_mine_distribution(config.mine_distribution);
+            minefield.set_mask(config.mask.clone());
+            // mines laid before the first click must be laid again on the final board
+            if minefield.mines_placed() {
+                minefield.reset_with_seed(minefield.seed());
+            }
+            minefield
+        }
+    };
+    minefield.set_practice_mode(minefield.practice_mode() || config.practice);
+
+    println!(
+        "Game with {} x {}, {} mines ({:.1}%)",
+        minefield.rows(),
+        minefield.cols(),
+        minefield.mine_count(),
+        minefield.mines_percent()
+    );
+    println!("Seed {}", minefield.seed());
+    if minefield.topology() != Topology::Plane {
+        println!("Topology {}", minefield.topology());
+    }
+    if *minefield.neighbourhood() != Neighbourhood::Touching {
+        println!("Neighbourhood {}", minefield.neighbourhood());
+    }
+    if let Some(mask) = minefield.mask() {
+        println!("Masked board, {} tiles per layer", mask.cell_count());
+    }
+    if minefield.depth() > 1 {
+        println!(
+            "{} layers, page up and page down to move between them",
+            minefield.depth()
+        );
+    }
+
+    let mut recorder = match (&config.record, &player) {
+        (Some(_), None) => Some(Recorder::new(&minefield)),
+        _ => None,
+    };
+
+    let tile_size = (config.tile_width, config.tile_height);
+    let (board_origin, (win_width, board_height)) =
+        board_layout(&minefield, tile_size, config.tile_gap);
+
+    // the status bar sits where the board would start, pushing the board down,
+    // and spans the board without its trailing gap whatever the grid
+    let status_bar_height = StatusBarRenderer::height(tile_size, config.tile_gap);
+    let status_bar_area = Rect::new(
+        board_origin.0 as i32,
+        board_origin.1 as i32,
+        win_width - 2 * board_origin.0 as u32 - config.tile_gap as u32,
+        tile_size.1 as u32,
+    );
+    let origin = (board_origin.0, board_origin.1 + status_bar_height);
+    let win_height = board_height + status_bar_height as u32;
+
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+
+    let window = video_subsystem
+        .window(&window_title(&minefield, 0), win_width, win_height)
+        .position_centered()
+        .opengl()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    let texture_creator = canvas.texture_creator();
+    let mut minefield_renderer = MinefieldRenderer::new(
+        &texture_creator,
+        &ttf_context,
+        &minefield,
+        tile_size,
+        config.tile_gap,
+        origin,
+    )
+    .unwrap();
+    let mut status_bar_renderer =
+        StatusBarRenderer::new(&texture_creator, &ttf_context, status_bar_area)
+            .map_err(|e| e.to_string())?;
+
+    minefield_renderer.clear_background(&mut canvas);
+    canvas.present();
+
+    let mut event_pump = sdl_context.event_pump()?;
+
+    let mut last_state = minefield.state();
+    let mut last_seed = minefield.seed();
+    // undo then redo wins the same game again, it is only recorded once
+    let mut score_recorded = false;
+    let mut board_changed = true;
+    let mut last_title = window_title(&minefield, 0);
+
+    'running: loop {
+        // event loop
+        for event in event_pump.poll_iter() {
+            let mut action = None;
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::MouseButtonDown {
+                    x, y, mouse_btn, ..
+                } => {
+                    let point = Point::new(x, y);
+                    if mouse_btn == MouseButton::Left && status_bar_renderer.is_reset_button(point)
+                    {
+                        action = Some(Move::Reset(rand::random()));
+                    } else if let Some(clicked_tile) = minefield_renderer.get_tile_index(point) {
+                        match mouse_btn {
+                            MouseButton::Left => action = Some(Move::Uncover(clicked_tile)),
+                            MouseButton::Right => action = Some(Move::Flag(clicked_tile)),
+                            _ => {}
+                        }
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    ..
+                } => action = Some(Move::Reset(rand::random())),
+                Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    ..
+                } => match minefield.save(Path::new(&config.save_file)) {
+                    Ok(()) => println!("Game saved to {}", config.save_file),
+                    Err(e) => eprintln!("{e}"),
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::E),
+                    ..
+                } => {
+                    let name = format!("mines-{}", minefield.seed());
+                    let text_path = format!("{name}.txt");
+                    match export::write_text(&minefield, Path::new(&text_path)) {
+                        Ok(()) => println!("Board exported to {text_path}"),
+                        Err(e) => eprintln!("{e}"),
+                    }
+                    // the image only shows the layer on screen
+                    let png_path = if minefield.depth() > 1 {
+                        format!("{name}-layer{}.png", minefield_renderer.layer() + 1)
+                    } else {
+                        format!("{name}.png")
+                    };
+                    match export_png(
+                        &ttf_context,
+                        &minefield,
+                        minefield_renderer.layer(),
+                        tile_size,
+                        config.tile_gap,
+                        Path::new(&png_path),
+                    ) {
+                        Ok(()) => println!("Board exported to {png_path}"),
+                        Err(e) => eprintln!("cannot export {png_path}: {e}"),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::T),
+                    ..
+                } => action = Some(Move::Restart),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Z),
+                    ..
+                } => action = Some(Move::Undo),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Y),
+                    ..
+                } => action = Some(Move::Redo),
+                // move between the layers of a 3D board
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageUp | Keycode::Up),
+                    ..
+                } => {
+                    let layer = minefield_renderer.layer().saturating_sub(1);
+                    minefield_renderer.set_layer(layer);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageDown | Keycode::Down),
+                    ..
+                } => {
+                    let layer = (minefield_renderer.layer() + 1).min(minefield.depth() - 1);
+                    minefield_renderer.set_layer(layer);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => {
+                    minefield_renderer.set_heatmap(minefield_renderer.heatmap().next());
+                    board_changed = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    ..
+                } => {
+                    if let Some(player) = player.as_mut() {
+                        board_changed |= player.step(&mut minefield);
+                    }
+                }
+                _ => {}
+            }
+
+            // moves only come from the replay while one is played back
+            if let (Some(action), None) = (action, &player) {
+                action.apply(&mut minefield);
+                board_changed = true;
+                if matches!(action, Move::Reset(_) | Move::Restart) {
+                    score_recorded = false;
+                    status_bar_renderer.set_new_record(false);
+                }
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder.record(action);
+                }
+            }
+        }
+
+        if let Some(player) = player.as_mut() {
+            board_changed |= player.advance(&mut minefield);
+        }
+
+        // the heatmap follows the game, worked out only when a move was played
+        if board_changed {
+            let probabilities =
+                if minefield_renderer.heatmap() == Heatmap::Off || minefield.is_over() {
+                    None
+                } else {
+                    match solver::probabilities(&minefield) {
+                        Ok(probabilities) => Some(probabilities),
+                        Err(e) => {
+                            eprintln!("no heatmap: {e}");
+                            minefield_renderer.set_heatmap(Heatmap::Off);
+                            None
+                        }
+                    }
+                };
+            minefield_renderer.set_probabilities(probabilities);
+            board_changed = false;
+        }
+
+        if let Some(e) = minefield.take_generation_error() {
+            eprintln!("{e}");
+        }
+
+        if minefield.seed() != last_seed {
+            println!("Seed {}", minefield.seed());
+            last_seed = minefield.seed();
+        }
+        let title = window_title(&minefield, minefield_renderer.layer());
+        if title != last_title {
+            canvas
+                .window_mut()
+                .set_title(&title)
+                .map_err(|e| e.to_string())?;
+            last_title = title;
+        }
+
+        // draw on canvas
+        minefield_renderer.clear_background(&mut canvas);
+        minefield_renderer
+            .draw_edges(&mut canvas, &minefield)
+            .map_err(|e| e.to_string())?;
+        minefield_renderer
+            .draw_tiles(&mut canvas, &minefield)
+            .map_err(|e| e.to_string())?;
+        status_bar_renderer
+            .draw(&mut canvas, &minefield)
+            .map_err(|e| e.to_string())?;
+
+        // refresh displayed canvas
+        canvas.present();
+
+        // frame rate limit
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 30));
+
+        // The rest of the game loop goes here...
+        if minefield.state() != last_state {
+            match minefield.state() {
+                GameState::Won => println!("WINNER WINNER"),
+                GameState::Lost => println!("BOOM"),
+                _ => {}
+            }
+            if minefield.is_over() {
+                print_stats(&Stats::new(&minefield));
+            }
+            // replays, practice games and variant boards stay out of the tables
+            if minefield.state() == GameState::Won
+                && !score_recorded
+                && player.is_none()
+                && !minefield.practice_mode()
+                && minefield.grid() == Grid::Square
+                && minefield.topology() == Topology::Plane
+                && minefield.depth() == 1
+                && *minefield.neighbourhood() == Neighbourhood::Touching
+                && minefield.mine_distribution() == MineDistribution::default()
+                && minefield.mask().is_none()
+            {
+                let new_record = record_score(&config, &minefield);
+                status_bar_renderer.set_new_record(new_record);
+                score_recorded = true;
+            }
+            last_state = minefield.state();
+        }
+    }
+
+    if let (Some(recorder), Some(path)) = (recorder, &config.record) {
+        recorder.replay().save(Path::new(path))?;
+        println!("Replay written to {path}");
+    }
+
+    Ok(())
+}
+
+fn print_stats(stats: &Stats) {
+    println!(
+        "time {:.2}s, 3BV {}/{}, clicks {} (left {}, right {}, chord {})",
+        stats.elapsed.as_secs_f64(),
+        stats.three_bv_solved,
+        stats.three_bv,
+        stats.clicks.total(),
+        stats.clicks.left,
+        stats.clicks.right,
+        stats.clicks.chord
+    );
+    println!(
+        "3BV/s {:.2}, IOE {:.2}, completion {:.0}%",
+        stats.three_bv_per_second(),
+        stats.ioe(),
+        100.0 * stats.completion()
+    );
+}
+
+/// Adds the won game to the tables, returns true if it is a new record.
+fn record_score(config: &Config, minefield: &Minefield) -> bool {
+    let path = Path::new(&config.scores_file);
+    let result = HighScores::load(path).and_then(|mut high_scores| {
+        let is_record = high_scores.add(Score::new(minefield, &config.name));
+        high_scores.save(path).map(|_| is_record)
+    });
+    match result {
+        Ok(true) => {
+            println!("New record!");
+            true
+        }
+        Ok(false) => false,
+        Err(e) => {
+            eprintln!("Cannot save high score: {e}");
+            false
+        }
+    }
+}
+
+fn window_title(minefield: &Minefield, layer: usize) -> String {
+    if minefield.depth() > 1 {
+        format!(
+            "mines - seed {} - layer {}/{}",
+            minefield.seed(),
+            layer + 1,
+            minefield.depth()
+        )
+    } else {
+        format!("mines - seed {}", minefield.seed())
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::neighbourhood::Neighbourhood;
+use crate::topology::Topology;
+
+/// Shape of the tiles and which of them touch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Grid {
+    /// Square tiles, eight neighbours each.
+    Square,
+    /// Pointy-top hexagons with odd rows shifted half a tile right, six neighbours each.
+    Hex,
+    /// Alternating triangles, pointing up where row + column is even. Edge and
+    /// corner contacts make twelve neighbours each.
+    Triangle,
+}
+
+const SQUARE_OFFSETS: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const HEX_EVEN_ROW_OFFSETS: [(i64, i64); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD_ROW_OFFSETS: [(i64, i64); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+// three tiles touch the apex, five the base
+const TRIANGLE_UP_OFFSETS: [(i64, i64); 12] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -2),
+    (0, -1),
+    (0, 1),
+    (0, 2),
+    (1, -2),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+];
+const TRIANGLE_DOWN_OFFSETS: [(i64, i64); 12] = [
+    (-1, -2),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (-1, 2),
+    (0, -2),
+    (0, -1),
+    (0, 1),
+    (0, 2),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl Grid {
+    /// Row and column steps from a tile to its neighbours, before any wrapping.
+    pub fn neighbour_offsets(&self, row: usize, col: usize) -> &'static [(i64, i64)] {
+        match self {
+            Grid::Square => &SQUARE_OFFSETS,
+            Grid::Hex if row.is_multiple_of(2) => &HEX_EVEN_ROW_OFFSETS,
+            Grid::Hex => &HEX_ODD_ROW_OFFSETS,
+            Grid::Triangle if Grid::points_up(row, col) => &TRIANGLE_UP_OFFSETS,
+            Grid::Triangle => &TRIANGLE_DOWN_OFFSETS,
+        }
+    }
+
+    /// Whether the triangle at a position points up, on a triangle grid.
+    pub fn points_up(row: usize, col: usize) -> bool {
+        (row + col).is_multiple_of(2)
+    }
+
+    /// Highest danger level a tile can show.
+    pub fn max_danger(&self) -> i32 {
+        match self {
+            Grid::Square => 8,
+            Grid::Hex => 6,
+            Grid::Triangle => 12,
+        }
+    }
+
+    /// Checks that the neighbours of tiles of this grid can be picked by offsets.
+    pub fn validate_neighbourhood(&self, neighbourhood: &Neighbourhood) -> Result<(), String> {
+        if neighbourhood.offsets().is_some() && *self != Grid::Square {
+            return Err(format!(
+                "{self} boards only use touching neighbours, not {neighbourhood}"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that tiles of this grid can be stacked in layers.
+    pub fn validate_depth(&self, depth: usize) -> Result<(), String> {
+        if depth > 1 && *self != Grid::Square {
+            return Err(format!("{self} boards cannot have more than one layer"));
+        }
+        Ok(())
+    }
+
+    /// Checks that the edges of a board of this grid can be glued as asked.
+    pub fn validate_topology(
+        &self,
+        topology: Topology,
+        rows: usize,
+        cols: usize,
+    ) -> Result<(), String> {
+        match self {
+            Grid::Square => return Ok(()),
+            // the half tile shift of odd rows must line up across the glued edges
+            Grid::Hex => {}
+            // so must the up and down triangles
+            Grid::Triangle => {
+                if topology.wraps_cols() && !cols.is_multiple_of(2) {
+                    return Err(format!(
+                        "{self} boards need an even number of columns for the {topology} topology"
+                    ));
+                }
+            }
+        }
+        if topology.flips_rows() {
+            return Err(format!("{self} boards cannot use the {topology} topology"));
+        }
+        if topology.wraps_rows() && !rows.is_multiple_of(2) {
+            return Err(format!(
+                "{self} boards need an even number of rows for the {topology} topology"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Grid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Grid, String> {
+        match s {
+            "square" => Ok(Grid::Square),
+            "hex" => Ok(Grid::Hex),
+            "triangle" => Ok(Grid::Triangle),
+            _ => Err(format!("unknown grid '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Grid::Square => "square",
+            Grid::Hex => "hex",
+            Grid::Triangle => "triangle",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minefield::{FirstClick, Minefield, TileContent};
+    use std::collections::BTreeSet;
+
+    /// Neighbours of a tile on a flat 5 x 6 board.
+    fn neighbours(grid: Grid, row: usize, col: usize) -> BTreeSet<(usize, usize)> {
+        let mut minefield = Minefield::with_mine_count(5, 6, 0, FirstClick::Safe, 0);
+        minefield.set_grid(grid);
+        let tile = minefield.position_to_tile(0, row, col);
+        minefield.get_neighbours(tile).into_iter().collect()
+    }
+
+    fn set(tiles: &[(usize, usize)]) -> BTreeSet<(usize, usize)> {
+        tiles.iter().copied().collect()
+    }
+
+    #[test]
+    fn square_neighbours() {
+        assert_eq!(
+            neighbours(Grid::Square, 2, 2),
+            set(&[
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 1),
+                (2, 3),
+                (3, 1),
+                (3, 2),
+                (3, 3)
+            ])
+        );
+        assert_eq!(
+            neighbours(Grid::Square, 0, 3),
+            set(&[(0, 2), (0, 4), (1, 2), (1, 3), (1, 4)])
+        );
+    }
+
+    #[test]
+    fn hex_neighbours() {
+        // even rows lean left, odd rows lean right
+        assert_eq!(
+            neighbours(Grid::Hex, 2, 2),
+            set(&[(1, 1), (1, 2), (2, 1), (2, 3), (3, 1), (3, 2)])
+        );
+        assert_eq!(
+            neighbours(Grid::Hex, 1, 2),
+            set(&[(0, 2), (0, 3), (1, 1), (1, 3), (2, 2), (2, 3)])
+        );
+        assert_eq!(neighbours(Grid::Hex, 0, 0), set(&[(0, 1), (1, 0)]));
+        assert_eq!(
+            neighbours(Grid::Hex, 1, 0),
+            set(&[(0, 0), (0, 1), (1, 1), (2, 0), (2, 1)])
+        );
+    }
+
+    #[test]
+    fn triangle_neighbours() {
+        // (2, 2) points up, (2, 3) points down
+        assert_eq!(
+            neighbours(Grid::Triangle, 2, 2),
+            set(&[
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 0),
+                (2, 1),
+                (2, 3),
+                (2, 4),
+                (3, 0),
+                (3, 1),
+                (3, 2),
+                (3, 3),
+                (3, 4),
+            ])
+        );
+        assert_eq!(
+            neighbours(Grid::Triangle, 2, 3),
+            set(&[
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (1, 4),
+                (1, 5),
+                (2, 1),
+                (2, 2),
+                (2, 4),
+                (2, 5),
+                (3, 2),
+                (3, 3),
+                (3, 4),
+            ])
+        );
+        assert_eq!(
+            neighbours(Grid::Triangle, 0, 0),
+            set(&[(0, 1), (0, 2), (1, 0), (1, 1), (1, 2)])
+        );
+        assert_eq!(
+            neighbours(Grid::Triangle, 4, 5),
+            set(&[(3, 3), (3, 4), (3, 5), (4, 3), (4, 4)])
+        );
+    }
+
+    #[test]
+    fn layered_neighbours() {
+        let mut minefield = Minefield::with_depth(3, 3, 3, 26, FirstClick::Safe, 0);
+        assert_eq!(minefield.max_danger(), 26);
+
+        // the middle of the cube touches every other tile
+        let centre = minefield.position_to_tile(1, 1, 1);
+        let all: BTreeSet<_> = (0..27)
+            .filter(|tile| *tile != centre)
+            .map(|tile| minefield.tile_to_indices(tile))
+            .collect();
+        let around: BTreeSet<_> = minefield.get_neighbours(centre).into_iter().collect();
+        assert_eq!(around, all);
+
+        // a corner touches its own layer and the one below
+        let corner = minefield.position_to_tile(0, 0, 0);
+        let around: BTreeSet<_> = minefield.get_neighbours(corner).into_iter().collect();
+        assert_eq!(
+            around,
+            set(&[(0, 1), (1, 0), (1, 1), (3, 0), (3, 1), (4, 0), (4, 1)])
+        );
+
+        // a safe first click in the middle leaves a mine on every neighbour
+        minefield.uncover_tile(centre);
+        assert_eq!(minefield.get_tile_content(centre), TileContent::Danger(26));
+    }
+}
@@ -5,6 +5,14 @@ use mines::Config;
 pub fn main() {
     let config = Config::build();
 
+    if config.list_scores || config.clear_scores {
+        if let Err(e) = mines::scores::run(&config) {
+            eprintln!("Cannot manage high scores: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
     if let Err(e) = mines::run(config) {
         eprintln!("Game ran into an error: {e}");
         process::exit(1);
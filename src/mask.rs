@@ -0,0 +1,313 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which cells of a board exist, for boards that are not full rectangles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mask {
+    rows: usize,
+    cols: usize,
+    /// Row after row.
+    cells: Vec<bool>,
+}
+
+impl Mask {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Whether the cell exists, cells past the edges do not.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols && self.cells[row * self.cols + col]
+    }
+
+    /// Number of cells that exist.
+    pub fn cell_count(&self) -> usize {
+        self.cells.iter().filter(|cell| **cell).count()
+    }
+
+    /// Reads a mask from a monochrome image, `.pbm` or `.png`, or from text.
+    pub fn load(path: &Path) -> Result<Mask, String> {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let contents =
+            fs::read(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        match extension {
+            Some("pbm") => Mask::from_pbm(&contents),
+            #[cfg(feature = "png")]
+            Some("png") => Mask::from_png(&contents),
+            #[cfg(not(feature = "png"))]
+            Some("png") => Err("PNG masks need the png feature".to_string()),
+            _ => {
+                let contents = String::from_utf8(contents)
+                    .map_err(|_| format!("{} is not a text file", path.display()))?;
+                Mask::from_text_str(&contents)
+            }
+        }
+    }
+
+    /// Reads a mask drawn as text: `x` is a cell, `.` or a space a hole. Short
+    /// lines end in holes and lines starting with `#` are comments.
+    pub fn from_text_str(contents: &str) -> Result<Mask, String> {
+        let mut lines = vec![];
+        for (number, line) in contents.lines().enumerate() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut row = vec![];
+            for (col, c) in line.trim_end().chars().enumerate() {
+                match c {
+                    'x' => row.push(true),
+                    '.' | ' ' => row.push(false),
+                    _ => {
+                        return Err(format!(
+                            "line {}, column {}: unknown cell '{c}'",
+                            number + 1,
+                            col + 1
+                        ))
+                    }
+                }
+            }
+            lines.push(row);
+        }
+
+        let cols = lines.iter().map(|row| row.len()).max().unwrap_or(0);
+        let rows = lines.len();
+        let cells = lines
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(cols, false);
+                row
+            })
+            .collect();
+        Mask::new(rows, cols, cells)
+    }
+
+    /// Reads a portable bitmap, plain (`P1`) or raw (`P4`): black pixels are cells.
+    pub fn from_pbm(contents: &[u8]) -> Result<Mask, String> {
+        // header tokens, skipping comments, and where the pixels start
+        let mut tokens = vec![];
+        let mut at = 0;
+        while tokens.len() < 3 && at < contents.len() {
+            match contents[at] {
+                b'#' => {
+                    while at < contents.len() && contents[at] != b'\n' {
+                        at += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => at += 1,
+                _ => {
+                    let start = at;
+                    while at < contents.len() && !contents[at].is_ascii_whitespace() {
+                        at += 1;
+                    }
+                    tokens.push(String::from_utf8_lossy(&contents[start..at]).to_string());
+                }
+            }
+        }
+        let [magic, width, height] = &tokens[..] else {
+            return Err("truncated bitmap header".to_string());
+        };
+        let cols: usize = width
+            .parse()
+            .map_err(|_| format!("invalid bitmap width '{width}'"))?;
+        let rows: usize = height
+            .parse()
+            .map_err(|_| format!("invalid bitmap height '{height}'"))?;
+
+        let cells = match magic.as_str() {
+            "P1" => contents[at..]
+                .iter()
+                .filter(|c| !c.is_ascii_whitespace())
+                .map(|c| match c {
+                    b'1' => Ok(true),
+                    b'0' => Ok(false),
+                    _ => Err(format!("invalid bitmap pixel '{}'", *c as char)),
+                })
+                .take(rows * cols)
+                .collect::<Result<Vec<bool>, String>>()?,
+            "P4" => {
+                // a single whitespace ends the header, rows are padded to whole bytes
+                let pixels = contents.get(at + 1..).unwrap_or_default();
+                let row_bytes = cols.div_ceil(8);
+                if pixels.len() < rows * row_bytes {
+                    return Err("truncated bitmap pixels".to_string());
+                }
+                (0..rows * cols)
+                    .map(|i| {
+                        let (row, col) = (i / cols, i % cols);
+                        pixels[row * row_bytes + col / 8] & (0x80 >> (col % 8)) != 0
+                    })
+                    .collect()
+            }
+            _ => return Err("not a portable bitmap".to_string()),
+        };
+        if cells.len() != rows * cols {
+            return Err("truncated bitmap pixels".to_string());
+        }
+        Mask::new(rows, cols, cells)
+    }
+
+    /// Reads a PNG image: dark, opaque pixels are cells.
+    #[cfg(feature = "png")]
+    pub fn from_png(contents: &[u8]) -> Result<Mask, String> {
+        let mut decoder = png::Decoder::new(contents);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).map_err(|e| e.to_string())?;
+
+        let channels = info.color_type.samples();
+        let cells = pixels[..info.buffer_size()]
+            .chunks(info.line_size)
+            .flat_map(|line| line[..info.width as usize * channels].chunks(channels))
+            .map(|pixel| {
+                let (value, alpha) = match pixel {
+                    [gray] => (*gray as u32, 255),
+                    [gray, alpha] => (*gray as u32, *alpha),
+                    [r, g, b] => ((*r as u32 + *g as u32 + *b as u32) / 3, 255),
+                    [r, g, b, alpha, ..] => ((*r as u32 + *g as u32 + *b as u32) / 3, *alpha),
+                    [] => (255, 0),
+                };
+                value < 128 && alpha >= 128
+            })
+            .collect();
+        Mask::new(info.height as usize, info.width as usize, cells)
+    }
+
+    fn new(rows: usize, cols: usize, cells: Vec<bool>) -> Result<Mask, String> {
+        if !cells.contains(&true) {
+            return Err("mask has no cells".to_string());
+        }
+        Ok(Mask { rows, cols, cells })
+    }
+}
+
+impl FromStr for Mask {
+    type Err = String;
+
+    /// Rows drawn as in `from_text_str`, separated by `/`.
+    fn from_str(s: &str) -> Result<Mask, String> {
+        Mask::from_text_str(&s.replace('/', "\n"))
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.rows {
+            if row > 0 {
+                write!(f, "/")?;
+            }
+            for col in 0..self.cols {
+                write!(f, "{}", if self.contains(row, col) { 'x' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_mask() {
+        let mask = Mask::from_text_str("x.x\n# a comment\nxx\n x\n").unwrap();
+        assert_eq!((mask.rows(), mask.cols()), (3, 3));
+        assert_eq!(mask.cell_count(), 5);
+        assert!(mask.contains(1, 1) && !mask.contains(1, 2) && !mask.contains(3, 0));
+        assert_eq!(mask.to_string(), "x.x/xx./.x.");
+        assert_eq!(mask.to_string().parse::<Mask>(), Ok(mask));
+
+        assert_eq!(
+            Mask::from_text_str("xx\nxo\n").err().as_deref(),
+            Some("line 2, column 2: unknown cell 'o'")
+        );
+        assert_eq!(
+            Mask::from_text_str("...\n. .\n").err().as_deref(),
+            Some("mask has no cells")
+        );
+    }
+
+    #[test]
+    fn plain_bitmap() {
+        let mask = Mask::from_pbm(b"P1\n# a comment\n3 2\n1 0 1\n0 1 1\n").unwrap();
+        assert_eq!(mask.to_string(), "x.x/.xx");
+        // pixels need no spaces between them
+        let mask = Mask::from_pbm(b"P1 3 2 101011").unwrap();
+        assert_eq!(mask.to_string(), "x.x/.xx");
+
+        assert_eq!(
+            Mask::from_pbm(b"P1\n3 2\n1 0 1\n0 2 1\n").err().as_deref(),
+            Some("invalid bitmap pixel '2'")
+        );
+        assert_eq!(
+            Mask::from_pbm(b"P1\n3 2\n1 0 1\n0 1\n").err().as_deref(),
+            Some("truncated bitmap pixels")
+        );
+    }
+
+    #[test]
+    fn raw_bitmap() {
+        // rows of 10 pixels take two bytes each
+        let mut contents = b"P4\n10 2\n".to_vec();
+        contents.extend([0b1000_0000, 0b0100_0000, 0xff, 0xc0]);
+        let mask = Mask::from_pbm(&contents).unwrap();
+        assert_eq!(mask.to_string(), "x........x/xxxxxxxxxx");
+
+        contents.pop();
+        assert_eq!(
+            Mask::from_pbm(&contents).err().as_deref(),
+            Some("truncated bitmap pixels")
+        );
+    }
+
+    #[test]
+    fn wrong_bitmap_header() {
+        assert_eq!(
+            Mask::from_pbm(b"P2\n3 2\n1 0 1\n0 1 1\n").err().as_deref(),
+            Some("not a portable bitmap")
+        );
+        assert_eq!(
+            Mask::from_pbm(b"P1\n3\n").err().as_deref(),
+            Some("truncated bitmap header")
+        );
+        assert_eq!(
+            Mask::from_pbm(b"P1\n3 two\n1 0 1\n0 1 1\n")
+                .err()
+                .as_deref(),
+            Some("invalid bitmap height 'two'")
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_mask() {
+        let mut contents = vec![];
+        let mut encoder = png::Encoder::new(&mut contents, 3, 2);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        let mut writer = encoder.write_header().unwrap();
+        // a transparent dark pixel is a hole
+        writer
+            .write_image_data(&[0, 255, 255, 255, 0, 0, 255, 255, 0, 255, 30, 200])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let mask = Mask::from_png(&contents).unwrap();
+        assert_eq!(mask.to_string(), "x../.xx");
+    }
+
+    #[cfg(not(feature = "png"))]
+    #[test]
+    fn png_needs_the_feature() {
+        let path = std::env::temp_dir().join(format!("mines-mask-{}.png", std::process::id()));
+        fs::write(&path, b"not read").unwrap();
+        let error = Mask::load(&path).err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.as_deref(), Some("PNG masks need the png feature"));
+    }
+}
@@ -0,0 +1,1406 @@
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::grid::Grid;
+use crate::mask::Mask;
+use crate::neighbourhood::Neighbourhood;
+use crate::solver;
+use crate::topology::Topology;
+
+mod layout;
+mod save;
+
+/// How many layouts no-guess generation tries before giving up.
+pub const NO_GUESS_MAX_ATTEMPTS: usize = 1000;
+
+/// Most mines a single tile can hold.
+pub const MAX_MINES_PER_TILE: u8 = 3;
+
+/// Share of the mined tiles holding two or three mines, the others hold one,
+/// and share of them holding anti-mines instead of mines.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MineDistribution {
+    pub double: f32,
+    pub triple: f32,
+    pub anti: f32,
+}
+
+impl MineDistribution {
+    /// Whether any tile can hold more than one mine.
+    pub fn allows_multiple(&self) -> bool {
+        self.double > 0.0 || self.triple > 0.0
+    }
+
+    /// Whether some tiles hold anti-mines, taking one off the danger level of
+    /// their neighbours.
+    pub fn has_anti_mines(&self) -> bool {
+        self.anti > 0.0
+    }
+
+    pub fn max_mines_per_tile(&self) -> u8 {
+        if self.triple > 0.0 {
+            3
+        } else if self.double > 0.0 {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let shares_valid = [self.double, self.triple]
+            .iter()
+            .all(|share| (0.0..=1.0).contains(share));
+        if !shares_valid || self.double + self.triple > 1.0 {
+            return Err(
+                "shares of double and triple mines must add up to at most 100%".to_string(),
+            );
+        }
+        if !(0.0..=1.0).contains(&self.anti) {
+            return Err("share of anti-mines must be between 0 and 100%".to_string());
+        }
+        Ok(())
+    }
+
+    /// Draws what the next mined tile holds.
+    fn sample<R: Rng>(&self, rng: &mut R) -> TileContent {
+        let draw: f32 = rng.gen();
+        let mines = if draw < self.triple {
+            3
+        } else if draw < self.triple + self.double {
+            2
+        } else {
+            1
+        };
+        if rng.gen::<f32>() < self.anti {
+            TileContent::AntiMine(mines)
+        } else {
+            TileContent::Mine(mines)
+        }
+    }
+}
+
+/// What the first uncovered tile of a game is guaranteed to be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FirstClick {
+    /// Mines are placed on reset, the first click can hit one.
+    Any,
+    /// The first uncovered tile is never a mine.
+    Safe,
+    /// The first uncovered tile is a zero, opening an area around it.
+    Opening,
+    /// Like `Opening`, and the whole board can be cleared without guessing.
+    NoGuess,
+}
+
+impl FromStr for FirstClick {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<FirstClick, String> {
+        match s {
+            "any" => Ok(FirstClick::Any),
+            "safe" => Ok(FirstClick::Safe),
+            "opening" => Ok(FirstClick::Opening),
+            "no-guess" => Ok(FirstClick::NoGuess),
+            _ => Err(format!("unknown first click mode '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for FirstClick {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            FirstClick::Any => "any",
+            FirstClick::Safe => "safe",
+            FirstClick::Opening => "opening",
+            FirstClick::NoGuess => "no-guess",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Progress of the game on a board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameState {
+    /// Nothing uncovered yet.
+    Ready,
+    Playing,
+    Won,
+    Lost,
+}
+
+/// What went wrong on a tile of a lost game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LossMark {
+    /// The mine that was uncovered.
+    Exploded,
+    /// A mine flag on a tile that is not a mine.
+    WrongFlag,
+    /// A mine that was not flagged.
+    MissedMine,
+}
+
+/// Clicks made on the board, whether or not they changed anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Clicks {
+    /// Uncovers of hidden tiles.
+    pub left: u32,
+    /// Flag changes.
+    pub right: u32,
+    /// Uncovers of revealed numbers, searching around them.
+    pub chord: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// Board state before or after a move, used for undo and redo. Once recorded
+/// it only keeps the tiles the move changed.
+#[derive(Clone)]
+struct Snapshot {
+    /// Row, column and the tile there.
+    tiles: Vec<(usize, usize, Tile)>,
+    mines_placed: bool,
+    mine_count: usize,
+    mine_locations: Vec<usize>,
+    mine_flag_counter: u32,
+    state: GameState,
+    exploded_tile: Option<usize>,
+}
+
+#[derive(Clone)]
+pub struct Minefield {
+    /// Layers stacked one under the other, `depth * rows` rows in all.
+    tiles: Vec<Vec<Tile>>,
+    rows: usize,
+    cols: usize,
+    depth: usize,
+    /// Mines laid on every new board, `mine_count` is what the current layout holds.
+    target_mine_count: usize,
+    grid: Grid,
+    neighbourhood: Neighbourhood,
+    topology: Topology,
+    /// Cells of every layer that exist, all of them without a mask.
+    mask: Option<Mask>,
+    mine_distribution: MineDistribution,
+    first_click: FirstClick,
+    seed: u64,
+    rng: ChaCha8Rng,
+    mines_placed: bool,
+    mine_count: usize,
+    mine_locations: Vec<usize>,
+    mine_flag_counter: u32,
+    state: GameState,
+    exploded_tile: Option<usize>,
+    elapsed: Duration,
+    started_at: Option<Instant>,
+    clicks: Clicks,
+    generation_error: Option<String>,
+    history_enabled: bool,
+    practice_mode: bool,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+impl Minefield {
+    /// A board with a share of its tiles mined, the mine count is rounded down.
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        mines_percent: f32,
+        first_click: FirstClick,
+        seed: u64,
+    ) -> Minefield {
+        let mine_count = mine_count_from_percent(rows * cols, mines_percent);
+        Minefield::with_mine_count(rows, cols, mine_count, first_click, seed)
+    }
+
+    pub fn with_mine_count(
+        rows: usize,
+        cols: usize,
+        mine_count: usize,
+        first_click: FirstClick,
+        seed: u64,
+    ) -> Minefield {
+        Minefield::with_depth(rows, cols, 1, mine_count, first_click, seed)
+    }
+
+    /// A volume of `depth` layers of `rows` x `cols` tiles.
+    pub fn with_depth(
+        rows: usize,
+        cols: usize,
+        depth: usize,
+        mine_count: usize,
+        first_click: FirstClick,
+        seed: u64,
+    ) -> Minefield {
+        let tiles = vec![
+            vec![
+                Tile {
+                    hidden: true,
+                    content: TileContent::Danger(0),
+                    flag: None
+                };
+                cols
+            ];
+            rows * depth
+        ];
+
+        let mut minefield = Minefield {
+            tiles,
+            rows,
+            cols,
+            depth,
+            target_mine_count: mine_count,
+            grid: Grid::Square,
+            neighbourhood: Neighbourhood::Touching,
+            topology: Topology::Plane,
+            mask: None,
+            mine_distribution: MineDistribution::default(),
+            first_click,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            mines_placed: false,
+            mine_count: 0,
+            mine_locations: vec![],
+            mine_flag_counter: 0,
+            state: GameState::Ready,
+            exploded_tile: None,
+            elapsed: Duration::ZERO,
+            started_at: None,
+            clicks: Clicks::default(),
+            generation_error: None,
+            history_enabled: true,
+            practice_mode: false,
+            undo_stack: vec![],
+            redo_stack: vec![],
+        };
+
+        minefield.reset_with_seed(seed);
+
+        minefield
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Number of layers, 1 on a flat board.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Tiles of the bounding volume, holes of the mask included.
+    pub fn total_tiles(&self) -> usize {
+        self.rows * self.cols * self.depth
+    }
+
+    /// Tiles that exist on the board.
+    pub fn tile_count(&self) -> usize {
+        match &self.mask {
+            Some(mask) => mask.cell_count() * self.depth,
+            None => self.total_tiles(),
+        }
+    }
+
+    /// Whether a tile is part of the board rather than a hole of the mask.
+    pub fn tile_exists(&self, tile_number: usize) -> bool {
+        let (_, row, col) = self.tile_to_position(tile_number);
+        self.mask
+            .as_ref()
+            .is_none_or(|mask| mask.contains(row, col))
+    }
+
+    /// Numbers of the tiles that exist, in order.
+    pub fn existing_tiles(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.total_tiles()).filter(|i| self.tile_exists(*i))
+    }
+
+    /// Highest danger level a tile can show: its number of neighbours.
+    pub fn max_danger(&self) -> i32 {
+        let in_layer = match self.neighbourhood.offsets() {
+            Some(offsets) => offsets.len() as i32,
+            None => self.grid.max_danger(),
+        };
+        // the tiles right above and below count too
+        let neighbours = if self.depth > 1 {
+            in_layer + 2 * (in_layer + 1)
+        } else {
+            in_layer
+        };
+        neighbours * self.mine_distribution.max_mines_per_tile() as i32
+    }
+
+    /// Lowest danger level a tile can show, below zero when anti-mines are laid.
+    pub fn min_danger(&self) -> i32 {
+        if self.mine_distribution.has_anti_mines() {
+            -self.max_danger()
+        } else {
+            0
+        }
+    }
+
+    pub fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    /// Changes the shape of the tiles, the danger levels of a placed layout follow.
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.grid = grid;
+        if self.mines_placed {
+            self.compute_danger_levels();
+        }
+    }
+
+    pub fn neighbourhood(&self) -> &Neighbourhood {
+        &self.neighbourhood
+    }
+
+    /// Changes which tiles are neighbours, the danger levels of a placed layout follow.
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        self.neighbourhood = neighbourhood;
+        if self.mines_placed {
+            self.compute_danger_levels();
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Changes how the edges wrap, the danger levels of a placed layout follow.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        if self.mines_placed {
+            self.compute_danger_levels();
+        }
+    }
+
+    pub fn mask(&self) -> Option<&Mask> {
+        self.mask.as_ref()
+    }
+
+    /// Cuts holes in every layer, used from the next layout on. The mask has
+    /// the rows and columns of a layer, cells past its edges are holes too.
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask;
+    }
+
+    pub fn mine_distribution(&self) -> MineDistribution {
+        self.mine_distribution
+    }
+
+    /// Changes how many mines tiles hold, used from the next layout on.
+    pub fn set_mine_distribution(&mut self, mine_distribution: MineDistribution) {
+        self.mine_distribution = mine_distribution;
+    }
+
+    pub fn first_click(&self) -> FirstClick {
+        self.first_click
+    }
+
+    /// Seed of the current layout, the same seed and first click give the same board.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Mines are only placed on the first uncover unless `FirstClick::Any` is used.
+    pub fn mines_placed(&self) -> bool {
+        self.mines_placed
+    }
+
+    /// Reason the last generation could not honour the first click mode, if any.
+    pub fn take_generation_error(&mut self) -> Option<String> {
+        self.generation_error.take()
+    }
+
+    pub fn mines_percent(&self) -> f32 {
+        100.0 * self.target_mine_count as f32 / self.tile_count() as f32
+    }
+
+    pub fn mine_locations(&self) -> &[usize] {
+        &self.mine_locations
+    }
+
+    pub fn mine_flag_counter(&self) -> u32 {
+        self.mine_flag_counter
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.state, GameState::Won | GameState::Lost)
+    }
+
+    pub fn exploded_tile(&self) -> Option<usize> {
+        self.exploded_tile
+    }
+
+    pub fn get_loss_mark(&self, tile_number: usize) -> Option<LossMark> {
+        if self.state != GameState::Lost {
+            return None;
+        }
+        if self.exploded_tile == Some(tile_number) {
+            return Some(LossMark::Exploded);
+        }
+
+        let charge = self.get_tile_content(tile_number).charge();
+        match self.get_tile_flag(tile_number) {
+            Some(Flag::Question) | None if charge != 0 => Some(LossMark::MissedMine),
+            Some(Flag::Question) | None => None,
+            Some(flag) if flag.charge() != charge => Some(LossMark::WrongFlag),
+            Some(_) => None,
+        }
+    }
+
+    /// Uncovers the mines only, leaving the safe tiles as the player left them.
+    pub fn reveal_mines(&mut self) {
+        for i in self.mine_locations.clone() {
+            let (row, col) = self.tile_to_indices(i);
+            self.tiles[row][col].uncover();
+        }
+    }
+
+    /// Starts a fresh board from a new random seed.
+    pub fn reset(&mut self) {
+        self.reset_with_seed(rand::random());
+    }
+
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        let total_tiles = self.total_tiles();
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.mine_flag_counter = 0;
+        self.mine_locations = vec![];
+        self.mines_placed = false;
+        self.state = GameState::Ready;
+        self.exploded_tile = None;
+        self.elapsed = Duration::ZERO;
+        self.started_at = None;
+        self.clicks = Clicks::default();
+        self.generation_error = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+
+        // reset all tiles
+        for i in 0..total_tiles {
+            self.reset_tile(i);
+        }
+
+        self.mine_count = self.target_mine_count;
+
+        if self.first_click == FirstClick::Any {
+            self.lay_mines(&[]);
+        }
+    }
+
+    /// Plays the same board again: the mine layout is kept once it has been placed.
+    pub fn restart(&mut self) {
+        if !self.mines_placed {
+            self.reset_with_seed(self.seed);
+            return;
+        }
+
+        for col in self.tiles.iter_mut() {
+            for tile in col.iter_mut() {
+                tile.hide();
+                tile.reset_flag();
+            }
+        }
+        self.mine_flag_counter = 0;
+        self.state = GameState::Ready;
+        self.exploded_tile = None;
+        self.elapsed = Duration::ZERO;
+        self.started_at = None;
+        self.clicks = Clicks::default();
+        self.generation_error = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    fn place_mines(&mut self, first_tile: usize) -> Result<(), String> {
+        let tile_count = self.tile_count();
+
+        // tiles that must stay free of mines for the first click
+        let mut excluded = vec![first_tile];
+        let wants_opening = matches!(self.first_click, FirstClick::Opening | FirstClick::NoGuess);
+        if wants_opening {
+            let neighbours: Vec<usize> = self
+                .get_neighbours(first_tile)
+                .iter()
+                .map(|(x, y)| self.indices_to_tile(*x, *y))
+                .collect();
+            // fall back to a safe tile when the board is too dense for an opening
+            if tile_count - 1 - neighbours.len() >= self.mine_count {
+                excluded.extend(neighbours);
+            } else if self.first_click == FirstClick::NoGuess {
+                self.lay_mines(&excluded);
+                return Err(format!(
+                    "{} mines leave no room for an opening, board may need guessing",
+                    self.mine_count
+                ));
+            }
+        }
+
+        self.lay_mines(&excluded);
+        if self.first_click != FirstClick::NoGuess {
+            return Ok(());
+        }
+
+        // retry random layouts until the solver clears one from the first tile
+        let mut attempts = 1;
+        while !solver::is_solvable(self, first_tile) {
+            if attempts == NO_GUESS_MAX_ATTEMPTS {
+                return Err(format!(
+                    "no guess-free layout found in {} attempts, mine density too high",
+                    attempts
+                ));
+            }
+            self.lay_mines(&excluded);
+            attempts += 1;
+        }
+
+        Ok(())
+    }
+
+    fn lay_mines(&mut self, excluded: &[usize]) {
+        let candidates: Vec<usize> = self
+            .existing_tiles()
+            .filter(|i| !excluded.contains(i))
+            .collect();
+        if self.mine_distribution == MineDistribution::default() {
+            let mine_locations = candidates
+                .into_iter()
+                .choose_multiple(&mut self.rng, self.mine_count);
+            self.set_mine_locations(
+                mine_locations
+                    .into_iter()
+                    .map(|i| (i, TileContent::Mine(1)))
+                    .collect(),
+            );
+            return;
+        }
+
+        let mut candidates = candidates;
+        candidates.shuffle(&mut self.rng);
+        let mut remaining = self.mine_count;
+        let mut mines = vec![];
+        for tile_number in candidates {
+            if remaining == 0 {
+                break;
+            }
+            let mut content = self.mine_distribution.sample(&mut self.rng);
+            if let TileContent::Mine(count) | TileContent::AntiMine(count) = &mut content {
+                *count = (*count as usize).min(remaining) as u8;
+                remaining -= *count as usize;
+            }
+            mines.push((tile_number, content));
+        }
+        // out of free tiles: stack the mines left over on the ones laid
+        let max_mines = self.mine_distribution.max_mines_per_tile();
+        for (_, content) in mines.iter_mut() {
+            if let TileContent::Mine(count) | TileContent::AntiMine(count) = content {
+                let added = remaining.min((max_mines - *count) as usize);
+                *count += added as u8;
+                remaining -= added;
+            }
+        }
+        self.set_mine_locations(mines);
+    }
+
+    /// Puts mines exactly on the given tiles, with what each holds, and
+    /// recomputes the danger levels.
+    fn set_mine_locations(&mut self, mines: Vec<(usize, TileContent)>) {
+        // clear a previous layout
+        for i in self.mine_locations.iter() {
+            let (row, col) = self.tile_to_indices(*i);
+            self.tiles[row][col].set_danger_level(0);
+        }
+
+        self.mine_locations = mines.iter().map(|(i, _)| *i).collect();
+        self.mine_count = mines
+            .iter()
+            .map(|(_, content)| content.charge().unsigned_abs() as usize)
+            .sum();
+        for (i, content) in mines {
+            let (row, col) = self.tile_to_indices(i);
+            self.tiles[row][col].content = content;
+        }
+
+        self.compute_danger_levels();
+        self.mines_placed = true;
+    }
+
+    /// Mines or anti-mines held by a tile, 0 for a safe one.
+    pub fn mines_on_tile(&self, tile_number: usize) -> u8 {
+        self.get_tile_content(tile_number).charge().unsigned_abs() as u8
+    }
+
+    /// Whether uncovering the tile opens the area around it: it is safe and no
+    /// mine or anti-mine touches it. With anti-mines a zero can hide both.
+    pub fn opens_area(&self, tile_number: usize) -> bool {
+        self.get_tile_content(tile_number) == TileContent::Danger(0)
+            && self
+                .get_neighbours(tile_number)
+                .iter()
+                .all(|(x, y)| self.tiles[*x][*y].content.charge() == 0)
+    }
+
+    fn compute_danger_levels(&mut self) {
+        for i in 0..self.total_tiles() {
+            if !self.tile_exists(i) {
+                continue;
+            }
+            let (row, col) = self.tile_to_indices(i);
+
+            // skip if tile is a mine
+            if !matches!(self.tiles[row][col].content, TileContent::Danger(_)) {
+                continue;
+            }
+
+            let danger_level = self
+                .get_neighbours(i)
+                .iter()
+                .map(|(x, y)| self.tiles[*x][*y].content.charge())
+                .sum();
+
+            self.tiles[row][col].set_danger_level(danger_level);
+        }
+    }
+
+    /// Neighbours as `(row, col)` indices, rows counting through the layers like
+    /// `tile_to_indices`.
+    pub fn get_neighbours(&self, tile_number: usize) -> Vec<(usize, usize)> {
+        let (layer, row, col) = self.tile_to_position(tile_number);
+        let mut neighbours = vec![];
+
+        for other_layer in layer.saturating_sub(1)..=(layer + 1).min(self.depth - 1) {
+            // tiles of other layers touch the one right above or below too
+            let straight = if other_layer == layer {
+                None
+            } else {
+                Some(&(0, 0))
+            };
+            let offsets = self
+                .neighbourhood
+                .offsets()
+                .unwrap_or_else(|| self.grid.neighbour_offsets(row, col));
+            for (j, k) in straight.into_iter().chain(offsets) {
+                // the topology decides what lies past the edges
+                let Some((x, y)) =
+                    self.topology
+                        .wrap(self.rows, self.cols, row as i64 + j, col as i64 + k)
+                else {
+                    continue;
+                };
+                let neighbour = (other_layer * self.rows + x, y);
+                if self.mask.as_ref().is_some_and(|mask| !mask.contains(x, y)) {
+                    continue;
+                }
+
+                // on narrow wrapping boards, tiles can meet themselves or the same
+                // neighbour twice
+                if neighbour == (layer * self.rows + row, col) || neighbours.contains(&neighbour) {
+                    continue;
+                }
+
+                neighbours.push(neighbour);
+            }
+        }
+
+        neighbours
+    }
+
+    /// Row and column of a tile, rows counting through the layers: row `r` of
+    /// layer `l` is row `l * rows + r`.
+    pub fn tile_to_indices(&self, tile_number: usize) -> (usize, usize) {
+        let (layer, row, col) = self.tile_to_position(tile_number);
+        (layer * self.rows + row, col)
+    }
+
+    pub fn indices_to_tile(&self, row: usize, col: usize) -> usize {
+        self.position_to_tile(row / self.rows, row % self.rows, col)
+    }
+
+    /// Layer, row and column of a tile.
+    pub fn tile_to_position(&self, tile_number: usize) -> (usize, usize, usize) {
+        let layer_size = self.rows * self.cols;
+        let layer = tile_number / layer_size;
+        let in_layer = tile_number % layer_size;
+        (layer, in_layer % self.rows, in_layer / self.rows)
+    }
+
+    pub fn position_to_tile(&self, layer: usize, row: usize, col: usize) -> usize {
+        layer * self.rows * self.cols + col * self.rows + row
+    }
+
+    pub fn uncover_tile(&mut self, tile_number: usize) {
+        if self.is_over() || !self.tile_exists(tile_number) {
+            return;
+        }
+
+        if self.tile_is_hidden(tile_number) {
+            self.clicks.left += 1;
+        } else {
+            self.clicks.chord += 1;
+        }
+
+        let before = self.snapshot();
+
+        if !self.mines_placed {
+            self.generation_error = self.place_mines(tile_number).err();
+        }
+
+        self.state = GameState::Playing;
+        self.start_timer();
+        self.uncover(tile_number);
+        self.update_state();
+
+        self.record(before);
+    }
+
+    fn uncover(&mut self, tile_number: usize) {
+        // a chord or flood fill may still be running after a mine went off
+        if self.state == GameState::Lost {
+            return;
+        }
+
+        let (row, col) = self.tile_to_indices(tile_number);
+        let was_hidden = self.tile_is_hidden(tile_number);
+
+        if was_hidden {
+            self.tiles[row][col].uncover();
+        }
+
+        match self.get_tile_content(tile_number) {
+            TileContent::Mine(_) | TileContent::AntiMine(_) => {
+                self.state = GameState::Lost;
+                self.exploded_tile = Some(tile_number);
+                self.reveal_mines();
+            }
+            TileContent::Danger(0) if self.opens_area(tile_number) => {
+                self.discover(tile_number);
+            }
+            TileContent::Danger(i) => {
+                if !was_hidden {
+                    self.auto_search_around(tile_number, i);
+                }
+            }
+        }
+
+        self.reset_tile_flag(tile_number);
+    }
+
+    /// Clears a tile for a new layout, leaving the mine list and flag counter
+    /// to the caller.
+    fn reset_tile(&mut self, tile_number: usize) {
+        let (row, col) = self.tile_to_indices(tile_number);
+        self.tiles[row][col].reset();
+    }
+
+    fn reset_tile_flag(&mut self, tile_number: usize) {
+        let (row, col) = self.tile_to_indices(tile_number);
+        if let Some(flag) = self.get_tile_flag(tile_number) {
+            self.mine_flag_counter -= flag.charge().unsigned_abs();
+        }
+        self.tiles[row][col].set_flag(None);
+    }
+
+    pub fn flag_tile(&mut self, tile_number: usize) {
+        // cannot flag revealed tiles, holes or finished games
+        if self.is_over() || !self.tile_exists(tile_number) {
+            return;
+        }
+        self.clicks.right += 1;
+        if !self.tile_is_hidden(tile_number) {
+            return;
+        }
+
+        let before = self.snapshot();
+
+        if self.state == GameState::Playing {
+            self.start_timer();
+        }
+
+        let can_flag_mines = self.mine_flag_counter < self.mine_count as u32;
+        let max_flagged = self.mine_distribution.max_mines_per_tile();
+        let (row, col) = self.tile_to_indices(tile_number);
+
+        let anti_mines = self.mine_distribution.has_anti_mines();
+
+        // mine flags count up to the most mines a tile can hold, then anti-mine
+        // flags do the same if there are any, then it turns to a question
+        let new_flag = match self.get_tile_flag(tile_number) {
+            Some(Flag::Mine(flagged)) if flagged < max_flagged && can_flag_mines => {
+                self.mine_flag_counter += 1;
+                Some(Flag::Mine(flagged + 1))
+            }
+            Some(Flag::Mine(flagged)) if anti_mines => {
+                self.mine_flag_counter -= flagged as u32 - 1;
+                Some(Flag::AntiMine(1))
+            }
+            Some(Flag::AntiMine(flagged)) if flagged < max_flagged && can_flag_mines => {
+                self.mine_flag_counter += 1;
+                Some(Flag::AntiMine(flagged + 1))
+            }
+            Some(Flag::Mine(flagged)) | Some(Flag::AntiMine(flagged)) => {
+                self.mine_flag_counter -= flagged as u32;
+                Some(Flag::Question)
+            }
+            Some(Flag::Question) => None,
+            // currently no flag
+            None => {
+                if can_flag_mines {
+                    self.mine_flag_counter += 1;
+                    Some(Flag::Mine(1))
+                } else {
+                    Some(Flag::Question)
+                }
+            }
+        };
+        self.tiles[row][col].set_flag(new_flag);
+
+        self.update_state();
+        self.record(before);
+    }
+
+    /// Keeping history can be turned off for boards driven by bots or the solver.
+    pub fn set_history_enabled(&mut self, enabled: bool) {
+        self.history_enabled = enabled;
+        if !enabled {
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+        }
+    }
+
+    /// In practice mode a move that hit a mine can be undone.
+    pub fn set_practice_mode(&mut self, practice_mode: bool) {
+        self.practice_mode = practice_mode;
+    }
+
+    pub fn practice_mode(&self) -> bool {
+        self.practice_mode
+    }
+
+    pub fn can_undo(&self) -> bool {
+        let mine_hit = self.state == GameState::Lost && !self.practice_mode;
+        !self.undo_stack.is_empty() && !mine_hit
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Rolls back the last move, a whole flood fill or chord counting as one.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        let previous = self.undo_stack.pop().unwrap();
+        let current = self.snapshot_of(&previous);
+        self.redo_stack.push(current);
+        self.restore(previous);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let next = match self.redo_stack.pop() {
+            Some(next) => next,
+            None => return false,
+        };
+        let current = self.snapshot_of(&next);
+        self.undo_stack.push(current);
+        self.restore(next);
+        true
+    }
+
+    fn snapshot(&self) -> Option<Snapshot> {
+        if !self.history_enabled {
+            return None;
+        }
+        let tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(row, tiles)| {
+                tiles
+                    .iter()
+                    .enumerate()
+                    .map(move |(col, tile)| (row, col, tile.clone()))
+            })
+            .collect();
+        Some(self.snapshot_with(tiles))
+    }
+
+    /// The current state of the tiles kept in `other`.
+    fn snapshot_of(&self, other: &Snapshot) -> Snapshot {
+        let tiles = other
+            .tiles
+            .iter()
+            .map(|(row, col, _)| (*row, *col, self.tiles[*row][*col].clone()))
+            .collect();
+        self.snapshot_with(tiles)
+    }
+
+    fn snapshot_with(&self, tiles: Vec<(usize, usize, Tile)>) -> Snapshot {
+        Snapshot {
+            tiles,
+            mines_placed: self.mines_placed,
+            mine_count: self.mine_count,
+            mine_locations: self.mine_locations.clone(),
+            mine_flag_counter: self.mine_flag_counter,
+            state: self.state,
+            exploded_tile: self.exploded_tile,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        for (row, col, tile) in snapshot.tiles {
+            self.tiles[row][col] = tile;
+        }
+        self.mines_placed = snapshot.mines_placed;
+        self.mine_count = snapshot.mine_count;
+        self.mine_locations = snapshot.mine_locations;
+        self.mine_flag_counter = snapshot.mine_flag_counter;
+        self.state = snapshot.state;
+        self.exploded_tile = snapshot.exploded_tile;
+    }
+
+    fn record(&mut self, before: Option<Snapshot>) {
+        if let Some(mut before) = before {
+            before
+                .tiles
+                .retain(|(row, col, tile)| *tile != self.tiles[*row][*col]);
+            // moves that changed nothing are not worth an undo step
+            if before.tiles.is_empty() && before.state == self.state {
+                return;
+            }
+            self.undo_stack.push(before);
+            self.redo_stack.clear();
+        }
+    }
+
+    fn update_state(&mut self) {
+        if self.state == GameState::Playing && self.check_win() {
+            self.state = GameState::Won;
+        }
+        if self.is_over() {
+            self.stop_timer();
+        }
+    }
+
+    /// Time spent playing, counted from the first uncover until the game is over.
+    pub fn elapsed(&self) -> Duration {
+        match self.started_at {
+            Some(started_at) => self.elapsed + started_at.elapsed(),
+            None => self.elapsed,
+        }
+    }
+
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    fn start_timer(&mut self) {
+        if self.started_at.is_none() {
+            self.started_at = Some(Instant::now());
+        }
+    }
+
+    fn stop_timer(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started_at = None;
+    }
+
+    pub fn get_tile(&self, tile_number: usize) -> &Tile {
+        let (row, col) = self.tile_to_indices(tile_number);
+        &self.tiles[row][col]
+    }
+
+    pub fn get_tile_content(&self, tile_number: usize) -> TileContent {
+        let (row, col) = self.tile_to_indices(tile_number);
+        self.tiles[row][col].content.clone()
+    }
+
+    pub fn get_tile_flag(&self, tile_number: usize) -> Option<Flag> {
+        let (row, col) = self.tile_to_indices(tile_number);
+        self.tiles[row][col].flag.clone()
+    }
+
+    pub fn tile_is_hidden(&self, tile_number: usize) -> bool {
+        let (row, col) = self.tile_to_indices(tile_number);
+        self.tiles[row][col].hidden
+    }
+
+    fn discover(&mut self, tile_number: usize) {
+        for (x, y) in self.get_neighbours(tile_number).iter() {
+            let neighbour_index = self.indices_to_tile(*x, *y);
+
+            // skip if already revealed
+            if !self.tile_is_hidden(neighbour_index) {
+                continue;
+            }
+
+            self.uncover(neighbour_index);
+        }
+    }
+
+    fn auto_search_around(&mut self, tile_number: usize, danger_level: i32) {
+        // TODO: optimize this
+        let mut local_mine_flag_count = 0;
+        let mut flagged = false;
+        for (x, y) in self.get_neighbours(tile_number).iter() {
+            if let Some(flag @ (Flag::Mine(_) | Flag::AntiMine(_))) = &self.tiles[*x][*y].flag {
+                local_mine_flag_count += flag.charge();
+                flagged = true;
+            }
+        }
+
+        // only an exact match will make it auto search, and never without a
+        // flag: a zero that opens nothing has a mine and an anti-mine around
+        if danger_level != local_mine_flag_count || !flagged {
+            return;
+        }
+
+        // uncover neighbours
+        for (x, y) in self.get_neighbours(tile_number).iter() {
+            let neighbour_index = self.indices_to_tile(*x, *y);
+            if !self.tile_is_hidden(neighbour_index) {
+                continue;
+            }
+            if self.tiles[*x][*y].flag.is_none() {
+                self.uncover(neighbour_index);
+            }
+        }
+    }
+
+    pub fn check_win(&self) -> bool {
+        for mine_index in self.mine_locations.iter() {
+            match self.get_tile_flag(*mine_index) {
+                // good if it counts the mines right, need all the others
+                Some(flag) if flag.charge() == self.get_tile_content(*mine_index).charge() => {}
+                Some(_) => {
+                    return false;
+                } // only mine flags of the right kind and count win
+                None => {
+                    // no flag on mine: no win
+                    return false;
+                }
+            }
+        }
+
+        // all mines are flagged as mines, need the rest of the board to be not hidden
+        for i in self.existing_tiles() {
+            if self.mine_locations.contains(&i) {
+                // skip mines
+                continue;
+            }
+            if self.tile_is_hidden(i) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Number of mines covering a share of the tiles, rounded down.
+pub fn mine_count_from_percent(total_tiles: usize, mines_percent: f32) -> usize {
+    (total_tiles as f32 * (mines_percent / 100.0)) as usize
+}
+
+/// Checks that a board can be played: it has tiles and at least one of them is safe.
+pub fn validate_board(
+    rows: usize,
+    cols: usize,
+    depth: usize,
+    mask: Option<&Mask>,
+    mine_count: usize,
+) -> Result<(), String> {
+    let mut size = if depth > 1 {
+        format!("{rows} x {cols} x {depth}")
+    } else {
+        format!("{rows} x {cols}")
+    };
+    let total_tiles = match mask {
+        Some(mask) => {
+            size += " masked";
+            mask.cell_count() * depth
+        }
+        None => rows * cols * depth,
+    };
+    if total_tiles == 0 {
+        return Err(format!("a {size} board has no tiles"));
+    }
+    if mine_count >= total_tiles {
+        return Err(format!(
+            "{mine_count} mines do not fit on a {size} board, at most {} can",
+            total_tiles - 1
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Tile {
+    hidden: bool,
+    content: TileContent,
+    flag: Option<Flag>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TileContent {
+    /// How many mines the tile holds.
+    Mine(u8),
+    /// How many anti-mines the tile holds, each taking one off its neighbours.
+    AntiMine(u8),
+    Danger(i32),
+}
+
+impl TileContent {
+    /// What the tile adds to the danger level of its neighbours.
+    pub fn charge(&self) -> i32 {
+        match self {
+            TileContent::Mine(mines) => *mines as i32,
+            TileContent::AntiMine(mines) => -(*mines as i32),
+            TileContent::Danger(_) => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Flag {
+    /// How many mines the player thinks the tile holds.
+    Mine(u8),
+    /// How many anti-mines the player thinks the tile holds.
+    AntiMine(u8),
+    Question,
+}
+
+impl Flag {
+    /// What the flagged tile would add to the danger level of its neighbours.
+    pub fn charge(&self) -> i32 {
+        match self {
+            Flag::Mine(mines) => *mines as i32,
+            Flag::AntiMine(mines) => -(*mines as i32),
+            Flag::Question => 0,
+        }
+    }
+}
+
+impl Tile {
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn content(&self) -> &TileContent {
+        &self.content
+    }
+
+    pub fn flag(&self) -> Option<&Flag> {
+        self.flag.as_ref()
+    }
+
+    pub fn uncover(&mut self) {
+        self.hidden = false;
+    }
+
+    pub fn hide(&mut self) {
+        self.hidden = true;
+    }
+
+    pub fn set_flag(&mut self, flag: Option<Flag>) {
+        self.flag = flag;
+    }
+
+    fn reset_flag(&mut self) {
+        self.flag = None;
+    }
+
+    pub fn set_as_mine(&mut self, mines: u8) {
+        self.content = TileContent::Mine(mines);
+    }
+
+    pub fn set_danger_level(&mut self, danger_level: i32) {
+        self.content = TileContent::Danger(danger_level);
+    }
+
+    pub fn reset(&mut self) {
+        self.hide();
+        self.set_danger_level(0);
+        self.reset_flag();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn misleading_zero_does_not_chord() {
+        // the mine and the anti-mine around tile 1 cancel out
+        let mut minefield = Minefield::with_mine_count(1, 6, 2, FirstClick::Safe, 0);
+        minefield.set_mine_distribution(MineDistribution {
+            double: 0.0,
+            triple: 0.0,
+            anti: 0.5,
+        });
+        minefield.set_mine_locations(vec![
+            (0, TileContent::Mine(1)),
+            (2, TileContent::AntiMine(1)),
+        ]);
+        assert_eq!(minefield.get_tile_content(1), TileContent::Danger(0));
+        assert!(!minefield.opens_area(1));
+
+        minefield.uncover_tile(1);
+        minefield.uncover_tile(1);
+        assert_eq!(minefield.state(), GameState::Playing);
+        assert!(minefield.tile_is_hidden(0) && minefield.tile_is_hidden(2));
+    }
+
+    fn hidden_tiles(minefield: &Minefield) -> Vec<usize> {
+        (0..minefield.total_tiles())
+            .filter(|i| minefield.tile_is_hidden(*i))
+            .collect()
+    }
+
+    #[test]
+    fn undo_opening() {
+        let mut minefield = Minefield::from_layout_str("....\n....\n...*\n").unwrap();
+        minefield.uncover_tile(0);
+        let opened = hidden_tiles(&minefield);
+        assert_eq!(opened, vec![11]);
+
+        assert!(minefield.undo());
+        assert_eq!(hidden_tiles(&minefield).len(), 12);
+        assert_eq!(minefield.state(), GameState::Ready);
+        assert!(minefield.redo());
+        assert_eq!(hidden_tiles(&minefield), opened);
+    }
+
+    #[test]
+    fn undo_chord() {
+        let mut minefield = Minefield::from_layout_str("*..\n...\n...\n").unwrap();
+        minefield.uncover_tile(4);
+        minefield.flag_tile(0);
+        minefield.uncover_tile(4);
+        assert_eq!(hidden_tiles(&minefield), vec![0]);
+
+        // the whole chord goes back in one step, the flag stays
+        assert!(minefield.undo());
+        assert_eq!(hidden_tiles(&minefield), vec![0, 1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(minefield.get_tile_flag(0), Some(Flag::Mine(1)));
+        assert_eq!(minefield.mine_flag_counter(), 1);
+        assert_eq!(minefield.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut minefield = Minefield::from_layout_str("*.*\n...\n*.*\n").unwrap();
+        minefield.uncover_tile(4);
+        minefield.uncover_tile(1);
+        assert!(minefield.undo());
+        assert!(minefield.can_redo());
+
+        minefield.uncover_tile(3);
+        assert!(!minefield.can_redo());
+        assert!(!minefield.redo());
+        assert_eq!(hidden_tiles(&minefield), vec![0, 1, 2, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn mine_hit_blocks_undo() {
+        let mut minefield = Minefield::from_layout_str("*..\n...\n...\n").unwrap();
+        minefield.uncover_tile(4);
+        minefield.uncover_tile(0);
+        assert_eq!(minefield.state(), GameState::Lost);
+        assert!(!minefield.can_undo());
+        assert!(!minefield.undo());
+        assert_eq!(minefield.state(), GameState::Lost);
+
+        // practice games take the hit back
+        minefield.set_practice_mode(true);
+        assert!(minefield.undo());
+        assert_eq!(minefield.state(), GameState::Playing);
+        assert_eq!(hidden_tiles(&minefield), vec![0, 1, 2, 3, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn safe_first_click() {
+        // dense enough that an unprotected first click would often hit a mine
+        for seed in 0..50 {
+            let mut minefield = Minefield::with_mine_count(8, 8, 60, FirstClick::Safe, seed);
+            let first = (seed as usize * 7) % minefield.total_tiles();
+            minefield.uncover_tile(first);
+            assert!(!minefield.mine_locations().contains(&first), "seed {seed}");
+            assert_ne!(minefield.state(), GameState::Lost, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn opening_first_click() {
+        for seed in 0..50 {
+            let mut minefield = Minefield::with_mine_count(9, 9, 40, FirstClick::Opening, seed);
+            let first = (seed as usize * 7) % minefield.total_tiles();
+            minefield.uncover_tile(first);
+            assert!(minefield.opens_area(first), "seed {seed}");
+            assert!(
+                hidden_tiles(&minefield).len() < minefield.total_tiles() - 1,
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_guess_layouts_are_solvable() {
+        for seed in 0..20 {
+            let mut minefield = Minefield::with_mine_count(9, 9, 10, FirstClick::NoGuess, seed);
+            let first = (seed as usize * 7) % minefield.total_tiles();
+            minefield.uncover_tile(first);
+            assert_eq!(minefield.take_generation_error(), None, "seed {seed}");
+            assert!(minefield.opens_area(first), "seed {seed}");
+            assert!(solver::is_solvable(&minefield, first), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn dense_no_guess_gives_up() {
+        // no room for an opening at all
+        let mut minefield = Minefield::with_mine_count(5, 5, 20, FirstClick::NoGuess, 0);
+        minefield.uncover_tile(12);
+        assert_eq!(
+            minefield.take_generation_error(),
+            Some("20 mines leave no room for an opening, board may need guessing".to_string())
+        );
+        assert!(!minefield.mine_locations().contains(&12));
+
+        // room for an opening, but far too dense to ever be guess-free
+        let mut minefield = Minefield::with_mine_count(9, 9, 60, FirstClick::NoGuess, 0);
+        minefield.uncover_tile(40);
+        assert_eq!(
+            minefield.take_generation_error(),
+            Some(format!(
+                "no guess-free layout found in {NO_GUESS_MAX_ATTEMPTS} attempts, mine density too high"
+            ))
+        );
+        assert!(minefield.opens_area(40));
+    }
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::Path;
+
+use super::{FirstClick, Flag, Minefield, TileContent};
+
+impl Minefield {
+    /// Reads a hand-authored board, see `from_layout_str` for the format.
+    pub fn load_layout(path: &Path) -> Result<Minefield, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Minefield::from_layout_str(&contents)
+    }
+
+    /// Builds a board from a text grid, one line per row:
+    ///
+    /// - `.` hidden empty tile, `*` hidden mine
+    /// - `o` uncovered empty tile
+    /// - `F` flagged mine, `f` empty tile with a wrong mine flag
+    ///
+    /// Lines starting with `#` are comments. Danger levels are computed from
+    /// the mines in the grid.
+    pub fn from_layout_str(contents: &str) -> Result<Minefield, String> {
+        let mut grid: Vec<Vec<char>> = vec![];
+
+        for (i, line) in contents.lines().enumerate() {
+            let number = i + 1;
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let row: Vec<char> = line.chars().collect();
+            if let Some(first_row) = grid.first() {
+                if row.len() != first_row.len() {
+                    return Err(format!(
+                        "line {number}, column {}: expected {} tiles in the row",
+                        row.len().min(first_row.len()) + 1,
+                        first_row.len()
+                    ));
+                }
+            }
+            if let Some(col) = row.iter().position(|c| !".*oFf".contains(*c)) {
+                return Err(format!(
+                    "line {number}, column {}: unknown tile '{}'",
+                    col + 1,
+                    row[col]
+                ));
+            }
+            grid.push(row);
+        }
+
+        if grid.is_empty() {
+            return Err("layout has no tiles".to_string());
+        }
+
+        let rows = grid.len();
+        let cols = grid[0].len();
+        let mut minefield = Minefield::new(rows, cols, 0.0, FirstClick::Safe, 0);
+
+        let mut mine_locations = vec![];
+        for (row, line) in grid.iter().enumerate() {
+            for (col, c) in line.iter().enumerate() {
+                if matches!(c, '*' | 'F') {
+                    mine_locations
+                        .push((minefield.indices_to_tile(row, col), TileContent::Mine(1)));
+                }
+            }
+        }
+        minefield.target_mine_count = mine_locations.len();
+        minefield.set_mine_locations(mine_locations);
+
+        for (row, line) in grid.iter().enumerate() {
+            for (col, c) in line.iter().enumerate() {
+                let tile = &mut minefield.tiles[row][col];
+                match c {
+                    'o' => tile.uncover(),
+                    'F' | 'f' => {
+                        tile.set_flag(Some(Flag::Mine(1)));
+                        minefield.mine_flag_counter += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(minefield)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tiles() {
+        let minefield = Minefield::from_layout_str("# a corner\n*F.\nof.\n").unwrap();
+        assert_eq!((minefield.rows(), minefield.cols()), (2, 3));
+        assert_eq!(minefield.mine_count(), 2);
+        assert_eq!(minefield.mine_flag_counter(), 2);
+        assert_eq!(minefield.get_tile_content(0), TileContent::Mine(1));
+        assert_eq!(minefield.get_tile_content(2), TileContent::Mine(1));
+        assert_eq!(minefield.get_tile_flag(3), Some(Flag::Mine(1)));
+        assert!(!minefield.tile_is_hidden(1));
+        assert_eq!(minefield.get_tile_content(1), TileContent::Danger(2));
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let minefield = Minefield::from_layout_str("\n# top\n*.\n\n# middle\n..\n").unwrap();
+        assert_eq!((minefield.rows(), minefield.cols()), (2, 2));
+    }
+
+    #[test]
+    fn ragged_row() {
+        let error = Minefield::from_layout_str("...\n# comment\n..\n").err();
+        assert_eq!(
+            error.as_deref(),
+            Some("line 3, column 3: expected 3 tiles in the row")
+        );
+        let error = Minefield::from_layout_str("..\n...\n").err();
+        assert_eq!(
+            error.as_deref(),
+            Some("line 2, column 3: expected 2 tiles in the row")
+        );
+    }
+
+    #[test]
+    fn unknown_tile() {
+        let error = Minefield::from_layout_str("# comment\n..*\n.x.\n").err();
+        assert_eq!(error.as_deref(), Some("line 3, column 2: unknown tile 'x'"));
+    }
+
+    #[test]
+    fn empty_layout() {
+        let error = Minefield::from_layout_str("# nothing\n\n").err();
+        assert_eq!(error.as_deref(), Some("layout has no tiles"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which tiles count towards the danger level of a tile.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Neighbourhood {
+    /// The tiles touching it, as laid out by the grid.
+    Touching,
+    /// The eight tiles a chess knight reaches.
+    Knight,
+    /// The four tiles sharing an edge on a square grid.
+    VonNeumann,
+    /// The 5 x 5 square around it.
+    Radius2,
+    /// Row and column offsets read from a file or the command line.
+    Custom(Vec<(i64, i64)>),
+}
+
+const KNIGHT_OFFSETS: [(i64, i64); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const VON_NEUMANN_OFFSETS: [(i64, i64); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const RADIUS_2_OFFSETS: [(i64, i64); 24] = [
+    (-2, -2),
+    (-2, -1),
+    (-2, 0),
+    (-2, 1),
+    (-2, 2),
+    (-1, -2),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (-1, 2),
+    (0, -2),
+    (0, -1),
+    (0, 1),
+    (0, 2),
+    (1, -2),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (2, -2),
+    (2, -1),
+    (2, 0),
+    (2, 1),
+    (2, 2),
+];
+
+impl Neighbourhood {
+    /// Row and column steps to the neighbours, `None` when the grid decides.
+    pub fn offsets(&self) -> Option<&[(i64, i64)]> {
+        match self {
+            Neighbourhood::Touching => None,
+            Neighbourhood::Knight => Some(&KNIGHT_OFFSETS),
+            Neighbourhood::VonNeumann => Some(&VON_NEUMANN_OFFSETS),
+            Neighbourhood::Radius2 => Some(&RADIUS_2_OFFSETS),
+            Neighbourhood::Custom(offsets) => Some(offsets),
+        }
+    }
+
+    /// Reads a neighbourhood drawn as a grid: `o` is the tile, `x` its
+    /// neighbours and `.` the other tiles. Lines starting with `#` are comments.
+    pub fn load(path: &Path) -> Result<Neighbourhood, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Neighbourhood::from_picture_str(&contents)
+    }
+
+    pub fn from_picture_str(contents: &str) -> Result<Neighbourhood, String> {
+        let mut center = None;
+        let mut marked = vec![];
+
+        let lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with('#'));
+        for (row, (number, line)) in lines.enumerate() {
+            for (col, c) in line.trim_end().chars().enumerate() {
+                match c {
+                    'o' if center.is_some() => {
+                        return Err(format!(
+                            "line {}, column {}: second center tile",
+                            number + 1,
+                            col + 1
+                        ))
+                    }
+                    'o' => center = Some((row as i64, col as i64)),
+                    'x' => marked.push((row as i64, col as i64)),
+                    '.' => {}
+                    _ => {
+                        return Err(format!(
+                            "line {}, column {}: unknown tile '{c}'",
+                            number + 1,
+                            col + 1
+                        ))
+                    }
+                }
+            }
+        }
+
+        let (center_row, center_col) = center.ok_or("neighbourhood has no center tile 'o'")?;
+        if marked.is_empty() {
+            return Err("neighbourhood has no neighbour 'x'".to_string());
+        }
+        let offsets = marked
+            .into_iter()
+            .map(|(row, col)| (row - center_row, col - center_col))
+            .collect();
+        Ok(Neighbourhood::Custom(offsets))
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    /// A built-in name, or `custom` followed by `row,col` offsets.
+    fn from_str(s: &str) -> Result<Neighbourhood, String> {
+        match s {
+            "touching" => Ok(Neighbourhood::Touching),
+            "knight" => Ok(Neighbourhood::Knight),
+            "von-neumann" => Ok(Neighbourhood::VonNeumann),
+            "radius-2" => Ok(Neighbourhood::Radius2),
+            _ => {
+                let Some(list) = s.strip_prefix("custom ") else {
+                    return Err(format!("unknown neighbourhood '{s}'"));
+                };
+                let mut offsets = vec![];
+                for offset in list.split_whitespace() {
+                    let (row, col) = offset
+                        .split_once(',')
+                        .and_then(|(row, col)| Some((row.parse().ok()?, col.parse().ok()?)))
+                        .ok_or(format!("invalid neighbourhood offset '{offset}'"))?;
+                    if (row, col) == (0, 0) {
+                        return Err("neighbourhood offset '0,0' is the tile itself".to_string());
+                    }
+                    // a tile counted twice would only inflate the highest danger level
+                    if offsets.contains(&(row, col)) {
+                        return Err(format!("duplicate neighbourhood offset '{offset}'"));
+                    }
+                    offsets.push((row, col));
+                }
+                if offsets.is_empty() {
+                    return Err("custom neighbourhood has no offsets".to_string());
+                }
+                Ok(Neighbourhood::Custom(offsets))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Neighbourhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Neighbourhood::Touching => write!(f, "touching"),
+            Neighbourhood::Knight => write!(f, "knight"),
+            Neighbourhood::VonNeumann => write!(f, "von-neumann"),
+            Neighbourhood::Radius2 => write!(f, "radius-2"),
+            Neighbourhood::Custom(offsets) => {
+                write!(f, "custom")?;
+                for (row, col) in offsets.iter() {
+                    write!(f, " {row},{col}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(s: &str) -> Option<String> {
+        s.parse::<Neighbourhood>().err()
+    }
+
+    #[test]
+    fn custom_offsets() {
+        assert_eq!(
+            "custom -1,0 2,-3 0,1".parse::<Neighbourhood>(),
+            Ok(Neighbourhood::Custom(vec![(-1, 0), (2, -3), (0, 1)]))
+        );
+        let custom = Neighbourhood::Custom(vec![(1, 1), (-2, 0)]);
+        assert_eq!(custom.to_string().parse::<Neighbourhood>(), Ok(custom));
+    }
+
+    #[test]
+    fn malformed_custom_offsets() {
+        for (s, offset) in [
+            ("custom 1", "1"),
+            ("custom 1,", "1,"),
+            ("custom ,1", ",1"),
+            ("custom 1,x", "1,x"),
+            ("custom 1,2,3", "1,2,3"),
+            ("custom 1;2", "1;2"),
+        ] {
+            assert_eq!(
+                parse_error(s),
+                Some(format!("invalid neighbourhood offset '{offset}'")),
+                "{s}"
+            );
+        }
+        assert_eq!(
+            parse_error("custom "),
+            Some("custom neighbourhood has no offsets".to_string())
+        );
+        assert_eq!(
+            parse_error("custom"),
+            Some("unknown neighbourhood 'custom'".to_string())
+        );
+    }
+
+    #[test]
+    fn custom_offset_on_the_tile() {
+        assert_eq!(
+            parse_error("custom 1,0 0,0"),
+            Some("neighbourhood offset '0,0' is the tile itself".to_string())
+        );
+        assert_eq!(
+            parse_error("custom -0,+0"),
+            Some("neighbourhood offset '0,0' is the tile itself".to_string())
+        );
+    }
+
+    #[test]
+    fn duplicate_custom_offsets() {
+        assert_eq!(
+            parse_error("custom 1,0 0,1 1,0"),
+            Some("duplicate neighbourhood offset '1,0'".to_string())
+        );
+        // the same offset written differently
+        assert_eq!(
+            parse_error("custom -1,2 -1,+2"),
+            Some("duplicate neighbourhood offset '-1,+2'".to_string())
+        );
+    }
+}
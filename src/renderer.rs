@@ -0,0 +1,795 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::ttf;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::grid::Grid;
+use crate::minefield::{Flag, LossMark, Minefield, TileContent};
+
+/// Where the board starts and how big the window holding it must be.
+pub fn board_layout(
+    minefield: &Minefield,
+    tile_size: (usize, usize),
+    tile_gap: usize,
+) -> ((usize, usize), (u32, u32)) {
+    let origin = (5 * tile_gap, 5 * tile_gap);
+    let (rows, cols) = (minefield.rows(), minefield.cols());
+    let (board_width, board_height) = match minefield.grid() {
+        Grid::Square => (
+            (tile_size.0 + tile_gap) * cols,
+            (tile_size.1 + tile_gap) * rows,
+        ),
+        Grid::Hex => {
+            // odd rows stick out half a tile to the right
+            let shift = if rows > 1 {
+                (tile_size.0 + tile_gap) / 2
+            } else {
+                0
+            };
+            (
+                (tile_size.0 + tile_gap) * cols + shift,
+                hex_row_pitch(tile_size, tile_gap) * (rows - 1) + tile_size.1 + tile_gap,
+            )
+        }
+        // triangles interlock, each one starts half a tile after the previous
+        Grid::Triangle => (
+            (tile_size.0 + tile_gap) / 2 * (cols - 1) + tile_size.0 + tile_gap,
+            (tile_size.1 + tile_gap) * rows,
+        ),
+    };
+    let width = board_width + 2 * origin.0;
+    let height = board_height + 2 * origin.1;
+    (origin, (width as u32, height as u32))
+}
+
+/// Vertical distance between hex rows, which overlap by a quarter of a tile.
+fn hex_row_pitch(tile_size: (usize, usize), tile_gap: usize) -> usize {
+    tile_size.1 * 3 / 4 + tile_gap
+}
+
+/// Bounding box of a tile on screen.
+fn tile_rect(
+    grid: Grid,
+    row: usize,
+    col: usize,
+    tile_size: (usize, usize),
+    tile_gap: usize,
+    origin: (usize, usize),
+) -> Rect {
+    let (x, y) = match grid {
+        Grid::Square => (
+            origin.0 + col * (tile_size.0 + tile_gap),
+            origin.1 + row * (tile_size.1 + tile_gap),
+        ),
+        Grid::Hex => (
+            origin.0 + col * (tile_size.0 + tile_gap) + (row % 2) * (tile_size.0 + tile_gap) / 2,
+            origin.1 + row * hex_row_pitch(tile_size, tile_gap),
+        ),
+        Grid::Triangle => (
+            origin.0 + col * (tile_size.0 + tile_gap) / 2,
+            origin.1 + row * (tile_size.1 + tile_gap),
+        ),
+    };
+    Rect::new(x as i32, y as i32, tile_size.0 as u32, tile_size.1 as u32)
+}
+
+/// One pixel high slices filling the pointy-top hexagon inscribed in `zone`.
+fn hex_scanlines(zone: Rect) -> Vec<Rect> {
+    let half_width = zone.width() as f32 / 2.0;
+    let half_height = zone.height() as f32 / 2.0;
+    (0..zone.height())
+        .map(|dy| {
+            // full width in the middle half, narrowing to a point at the top and bottom
+            let v = (dy as f32 + 0.5 - half_height).abs();
+            let slice_half_width = if v <= half_height / 2.0 {
+                half_width
+            } else {
+                half_width * (half_height - v) / (half_height / 2.0)
+            };
+            Rect::new(
+                zone.x() + (half_width - slice_half_width).round() as i32,
+                zone.y() + dy as i32,
+                ((2.0 * slice_half_width).round() as u32).max(1),
+                1,
+            )
+        })
+        .collect()
+}
+
+/// Corners of the triangle inscribed in `zone`, apex first.
+fn triangle_corners(zone: Rect, points_up: bool) -> [Point; 3] {
+    let (apex_y, base_y) = if points_up {
+        (zone.top(), zone.bottom())
+    } else {
+        (zone.bottom(), zone.top())
+    };
+    [
+        Point::new(zone.center().x(), apex_y),
+        Point::new(zone.left(), base_y),
+        Point::new(zone.right(), base_y),
+    ]
+}
+
+/// One pixel high slices filling the triangle inscribed in `zone`.
+fn triangle_scanlines(zone: Rect, points_up: bool) -> Vec<Rect> {
+    let width = zone.width() as f32;
+    let height = zone.height() as f32;
+    (0..zone.height())
+        .filter_map(|dy| {
+            // distance from the apex decides the width of the slice
+            let from_apex = if points_up {
+                dy as f32 + 0.5
+            } else {
+                height - dy as f32 - 0.5
+            };
+            let slice_width = (width * from_apex / height).round() as u32;
+            if slice_width == 0 {
+                return None;
+            }
+            Some(Rect::new(
+                zone.x() + ((zone.width() - slice_width) / 2) as i32,
+                zone.y() + dy as i32,
+                slice_width,
+                1,
+            ))
+        })
+        .collect()
+}
+
+/// Whether `point` is inside the triangle inscribed in `zone`.
+fn triangle_contains(zone: Rect, points_up: bool, point: Point) -> bool {
+    let [a, b, c] = triangle_corners(zone, points_up);
+    // the point is inside when it is on the same side of all three edges
+    let side = |p: Point, q: Point| {
+        (q.x() - p.x()) as i64 * (point.y() - p.y()) as i64
+            - (q.y() - p.y()) as i64 * (point.x() - p.x()) as i64
+    };
+    let sides = [side(a, b), side(b, c), side(c, a)];
+    sides.iter().all(|s| *s >= 0) || sides.iter().all(|s| *s <= 0)
+}
+
+/// Whether `point` is inside the pointy-top hexagon inscribed in `zone`.
+fn hex_contains(zone: Rect, point: Point) -> bool {
+    let a = zone.width() as f32 / 2.0;
+    let b = zone.height() as f32 / 2.0;
+    let center = zone.center();
+    let dx = (point.x() - center.x()).abs() as f32;
+    let dy = (point.y() - center.y()).abs() as f32;
+    dx <= a && dy <= b - dx * (b / 2.0) / a
+}
+
+/// Renders one layer of the board offscreen with the in-game look and writes it
+/// as a PNG.
+pub fn export_png(
+    ttf_context: &ttf::Sdl2TtfContext,
+    minefield: &Minefield,
+    layer: usize,
+    tile_size: (usize, usize),
+    tile_gap: usize,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let (origin, (width, height)) = board_layout(minefield, tile_size, tile_gap);
+
+    let surface = Surface::new(width, height, PixelFormatEnum::RGBA32)?;
+    let mut canvas = surface.into_canvas()?;
+    let texture_creator = canvas.texture_creator();
+    let mut minefield_renderer = MinefieldRenderer::new(
+        &texture_creator,
+        ttf_context,
+        minefield,
+        tile_size,
+        tile_gap,
+        origin,
+    )?;
+    minefield_renderer.set_layer(layer);
+    minefield_renderer.clear_background(&mut canvas);
+    minefield_renderer.draw_edges(&mut canvas, minefield)?;
+    minefield_renderer.draw_tiles(&mut canvas, minefield)?;
+    canvas.present();
+
+    let surface = canvas.into_surface();
+    let pitch = surface.pitch() as usize;
+    let row_length = 4 * width as usize;
+    let pixels = surface
+        .without_lock()
+        .ok_or("cannot read rendered pixels")?;
+    let data: Vec<u8> = pixels
+        .chunks(pitch)
+        .flat_map(|row| row[..row_length].iter().copied())
+        .collect();
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&data)?;
+
+    Ok(())
+}
+
+/// Overlay showing the chance of hidden tiles to be mines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heatmap {
+    Off,
+    /// Tiles tinted by their chance.
+    Tint,
+    /// Tinted, with the chance written on the tiles.
+    Percent,
+}
+
+impl Heatmap {
+    /// The next overlay, back to `Off` after `Percent`.
+    pub fn next(self) -> Heatmap {
+        match self {
+            Heatmap::Off => Heatmap::Tint,
+            Heatmap::Tint => Heatmap::Percent,
+            Heatmap::Percent => Heatmap::Off,
+        }
+    }
+}
+
+pub struct MinefieldRenderer {
+    grid: Grid,
+    rows: usize,
+    /// Layer of a 3D board on screen.
+    layer: usize,
+    /// Screen box of each tile of a layer, `None` for the holes of a mask.
+    tiles_coords: Vec<Option<Rect>>,
+    tile_gap: usize,
+    heatmap: Heatmap,
+    /// Chance of the hidden tiles to be mines, given by the game when the board changes.
+    probabilities: Option<HashMap<usize, f64>>,
+    textures: MinefieldRendererTextures,
+}
+
+impl MinefieldRenderer {
+    pub fn new<C>(
+        texture_creator: &TextureCreator<C>,
+        ttf_context: &ttf::Sdl2TtfContext,
+        minefield: &Minefield,
+        tile_size: (usize, usize),
+        tile_gap: usize,
+        origin: (usize, usize),
+    ) -> Result<MinefieldRenderer, Box<dyn Error>> {
+        // compute where the tiles will be on the screen
+        let rows = minefield.rows();
+        let cols = minefield.cols();
+        let grid = minefield.grid();
+        let tiles_coords = (0..(rows * cols))
+            .map(|x: usize| {
+                minefield
+                    .tile_exists(x)
+                    .then(|| tile_rect(grid, x % rows, x / rows, tile_size, tile_gap, origin))
+            })
+            .collect();
+
+        let mut font =
+            ttf_context.load_font("assets/fonts/lm-mono-font/Lmmono12Regular-K7qoZ.otf", 128)?;
+        font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+        let textures = MinefieldRendererTextures::new(
+            font,
+            texture_creator,
+            minefield.min_danger(),
+            minefield.max_danger(),
+        )
+        .unwrap();
+
+        Ok(MinefieldRenderer {
+            grid,
+            rows,
+            layer: 0,
+            tiles_coords,
+            tile_gap,
+            heatmap: Heatmap::Off,
+            probabilities: None,
+            textures,
+        })
+    }
+
+    pub fn draw_tiles<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        minefield: &Minefield,
+    ) -> Result<(), Box<dyn Error>> {
+        canvas.set_draw_color(Color::RGB(240, 240, 240));
+
+        let probabilities = self
+            .probabilities
+            .as_ref()
+            .filter(|_| self.heatmap != Heatmap::Off);
+
+        // only the shown layer is drawn, `i` counts its tiles
+        for i in 0..self.tiles_coords.len() {
+            let Some(tile_zone) = self.tiles_coords[i] else {
+                continue;
+            };
+            let tile_number = self.layer * self.tiles_coords.len() + i;
+            self.fill_tile(canvas, tile_zone, i)?;
+            let draw_zone = &self.content_zone(tile_zone, i);
+
+            if let Some(mark) = minefield.get_loss_mark(tile_number) {
+                let charge = minefield.get_tile_content(tile_number).charge();
+                let (background, texture) = match mark {
+                    LossMark::Exploded => (Color::RGB(255, 150, 150), self.textures.mine(charge)),
+                    LossMark::WrongFlag => {
+                        (Color::RGB(255, 210, 120), &self.textures.tile_flag_wrong)
+                    }
+                    LossMark::MissedMine => (Color::RGB(200, 200, 200), self.textures.mine(charge)),
+                };
+                canvas.set_draw_color(background);
+                self.fill_tile(canvas, tile_zone, i)?;
+                canvas.set_draw_color(Color::RGB(240, 240, 240));
+                canvas.copy(texture, None, Some(*draw_zone))?;
+            } else if minefield.tile_is_hidden(tile_number) {
+                let probability =
+                    probabilities.and_then(|probabilities| probabilities.get(&tile_number));
+                if let Some(probability) = probability {
+                    canvas.set_draw_color(heat_color(*probability));
+                    self.fill_tile(canvas, tile_zone, i)?;
+                    canvas.set_draw_color(Color::RGB(240, 240, 240));
+                }
+                if let Some(flag) = minefield.get_tile_flag(tile_number) {
+                    match flag {
+                        Flag::Mine(_) | Flag::AntiMine(_) => canvas.copy(
+                            self.textures.flag(flag.charge()),
+                            None,
+                            Some(*draw_zone),
+                        )?,
+                        Flag::Question => canvas.copy(
+                            &self.textures.tile_flag_question,
+                            None,
+                            Some(*draw_zone),
+                        )?,
+                    }
+                } else if let Some(texture) = probability
+                    .filter(|_| self.heatmap == Heatmap::Percent)
+                    .and_then(|probability| self.textures.percent(*probability))
+                {
+                    canvas.copy(texture, None, Some(*draw_zone))?;
+                } else {
+                    canvas.copy(&self.textures.tile_blank, None, Some(*draw_zone))?;
+                }
+            } else if let Some(flag @ (Flag::Mine(_) | Flag::AntiMine(_))) =
+                minefield.get_tile_flag(tile_number)
+            {
+                // correctly flagged mine of a revealed board
+                canvas.copy(self.textures.flag(flag.charge()), None, Some(*draw_zone))?;
+            } else {
+                match minefield.get_tile_content(tile_number) {
+                    TileContent::Danger(level) => {
+                        if let Some(texture) = self.textures.danger(level) {
+                            canvas.copy(texture, None, Some(*draw_zone))?;
+                        }
+                    }
+                    content => {
+                        canvas.copy(
+                            self.textures.mine(content.charge()),
+                            None,
+                            Some(*draw_zone),
+                        )?;
+                    }
+                }
+            }
+
+            if minefield.depth() > 1 {
+                self.draw_ghosts(canvas, minefield, tile_zone, i)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hints at the tiles right above and below in the neighbouring layers: the
+    /// previous layer faded in the top left corner, the next one bottom right.
+    fn draw_ghosts<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        minefield: &Minefield,
+        tile_zone: Rect,
+        i: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = (tile_zone.width() / 3, tile_zone.height() / 3);
+        let corners = [
+            (self.layer.checked_sub(1), tile_zone.top_left()),
+            (
+                Some(self.layer + 1).filter(|layer| *layer < minefield.depth()),
+                tile_zone.bottom_right() - Point::new(width as i32, height as i32),
+            ),
+        ];
+
+        for (layer, corner) in corners {
+            let Some(layer) = layer else {
+                continue;
+            };
+            let ghost = layer * self.tiles_coords.len() + i;
+            let texture = match (
+                minefield.get_tile_flag(ghost),
+                minefield.get_tile_content(ghost),
+            ) {
+                (Some(flag @ (Flag::Mine(_) | Flag::AntiMine(_))), _) => {
+                    self.textures.flag(flag.charge())
+                }
+                _ if minefield.tile_is_hidden(ghost) => continue,
+                (_, TileContent::Danger(level)) => match self.textures.danger(level) {
+                    Some(texture) => texture,
+                    None => continue,
+                },
+                (_, content) => self.textures.mine(content.charge()),
+            };
+
+            // fade the hint by drawing the tile colour over it
+            let zone = Rect::new(corner.x(), corner.y(), width, height);
+            canvas.copy(texture, None, Some(zone))?;
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(240, 240, 240, 120));
+            canvas.fill_rect(zone)?;
+            canvas.set_blend_mode(BlendMode::None);
+        }
+        canvas.set_draw_color(Color::RGB(240, 240, 240));
+
+        Ok(())
+    }
+
+    pub fn layer(&self) -> usize {
+        self.layer
+    }
+
+    pub fn set_layer(&mut self, layer: usize) {
+        self.layer = layer;
+    }
+
+    pub fn heatmap(&self) -> Heatmap {
+        self.heatmap
+    }
+
+    /// Changes the overlay, drawn from the chances set with `set_probabilities`.
+    pub fn set_heatmap(&mut self, heatmap: Heatmap) {
+        self.heatmap = heatmap;
+    }
+
+    /// Chances the heatmap shows, `None` draws no overlay.
+    pub fn set_probabilities(&mut self, probabilities: Option<HashMap<usize, f64>>) {
+        self.probabilities = probabilities;
+    }
+
+    /// Fills the shape of a tile with the current draw colour.
+    fn fill_tile<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        tile_zone: Rect,
+        tile_number: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.grid {
+            Grid::Square => canvas.fill_rect(tile_zone)?,
+            Grid::Hex => canvas.fill_rects(&hex_scanlines(tile_zone))?,
+            Grid::Triangle => {
+                canvas.fill_rects(&triangle_scanlines(tile_zone, self.points_up(tile_number)))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Part of a tile where its number or flag is drawn, clear of slanted edges.
+    fn content_zone(&self, tile_zone: Rect, tile_number: usize) -> Rect {
+        match self.grid {
+            Grid::Square => tile_zone,
+            Grid::Hex => Rect::from_center(
+                tile_zone.center(),
+                tile_zone.width() * 3 / 4,
+                tile_zone.height() * 3 / 4,
+            ),
+            // around the centroid, a third of the way from the base
+            Grid::Triangle => {
+                let height = tile_zone.height() as i32;
+                let centroid_y = if self.points_up(tile_number) {
+                    tile_zone.y() + 2 * height / 3
+                } else {
+                    tile_zone.y() + height / 3
+                };
+                Rect::from_center(
+                    Point::new(tile_zone.center().x(), centroid_y),
+                    tile_zone.width() * 2 / 5,
+                    tile_zone.height() * 2 / 5,
+                )
+            }
+        }
+    }
+
+    fn points_up(&self, tile_number: usize) -> bool {
+        Grid::points_up(tile_number % self.rows, tile_number / self.rows)
+    }
+
+    /// Marks the edges that wrap around with strips in the margin: strips of the
+    /// same colour are glued together.
+    pub fn draw_edges<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        minefield: &Minefield,
+    ) -> Result<(), Box<dyn Error>> {
+        let topology = minefield.topology();
+        let (rows, cols) = (minefield.rows(), minefield.cols());
+        let gap = self.tile_gap as i32;
+        let strip = 2 * gap as u32;
+
+        // shades from blue to orange along the edge, so a flip shows as a reversal
+        let shade = |i: usize, n: usize| {
+            let t = if n > 1 {
+                i as f32 / (n - 1) as f32
+            } else {
+                0.0
+            };
+            Color::RGB(
+                (60.0 + 195.0 * t) as u8,
+                (120.0 + 40.0 * t) as u8,
+                (255.0 - 215.0 * t) as u8,
+            )
+        };
+
+        if topology.wraps_cols() {
+            for row in 0..rows {
+                let left = self.tiles_coords[minefield.indices_to_tile(row, 0)];
+                let right = self.tiles_coords[minefield.indices_to_tile(row, cols - 1)];
+                // only edges with tiles on both sides are glued
+                let (Some(left), Some(right)) = (left, right) else {
+                    continue;
+                };
+                canvas.set_draw_color(shade(row, rows));
+                canvas.fill_rect(Rect::new(
+                    left.x() - 3 * gap,
+                    left.y(),
+                    strip,
+                    left.height(),
+                ))?;
+                canvas.set_draw_color(shade(topology.glued_row(rows, row), rows));
+                canvas.fill_rect(Rect::new(
+                    right.right() + gap,
+                    right.y(),
+                    strip,
+                    right.height(),
+                ))?;
+            }
+        }
+
+        if topology.wraps_rows() {
+            canvas.set_draw_color(Color::RGB(120, 200, 120));
+            for col in 0..cols {
+                let top = self.tiles_coords[minefield.indices_to_tile(0, col)];
+                let bottom = self.tiles_coords[minefield.indices_to_tile(rows - 1, col)];
+                let (Some(top), Some(bottom)) = (top, bottom) else {
+                    continue;
+                };
+                canvas.fill_rect(Rect::new(top.x(), top.y() - 3 * gap, top.width(), strip))?;
+                canvas.fill_rect(Rect::new(
+                    bottom.x(),
+                    bottom.bottom() + gap,
+                    bottom.width(),
+                    strip,
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn clear_background<T: RenderTarget>(&self, canvas: &mut Canvas<T>) {
+        canvas.set_draw_color(Color::RGB(50, 50, 50));
+        canvas.clear();
+    }
+
+    pub fn get_tile_index(&self, point: Point) -> Option<usize> {
+        for (i, tile_zone) in self.tiles_coords.iter().enumerate() {
+            let Some(tile_zone) = tile_zone else {
+                continue;
+            };
+            let contains = match self.grid {
+                Grid::Square => tile_zone.contains_point(point),
+                Grid::Hex => hex_contains(*tile_zone, point),
+                Grid::Triangle => triangle_contains(*tile_zone, self.points_up(i), point),
+            };
+            if contains {
+                return Some(self.layer * self.tiles_coords.len() + i);
+            }
+        }
+        None
+    }
+}
+
+/// Colour of each danger level, the last one is reused past the end.
+const DANGER_COLORS: [(u8, u8, u8); 13] = [
+    (200, 200, 200),
+    (74, 200, 37),
+    (226, 200, 0),
+    (255, 200, 0),
+    (255, 151, 0),
+    (234, 39, 144),
+    (230, 120, 91),
+    (205, 50, 72),
+    (202, 0, 0),
+    (160, 0, 160),
+    (110, 30, 200),
+    (50, 70, 220),
+    (20, 20, 140),
+];
+
+/// Colour of anti-mines, their flags and negative danger levels.
+const ANTI_COLOR: (u8, u8, u8) = (30, 110, 210);
+
+/// Heatmap colour of a hidden tile: green when proven safe, red when proven a
+/// mine, from pale yellow to orange in between.
+fn heat_color(probability: f64) -> Color {
+    if probability <= 0.0 {
+        return Color::RGB(120, 210, 120);
+    }
+    if probability >= 1.0 {
+        return Color::RGB(215, 60, 60);
+    }
+    let mix = |low: u8, high: u8| (low as f64 + (high as f64 - low as f64) * probability) as u8;
+    Color::RGB(mix(250, 245), mix(245, 140), mix(200, 90))
+}
+
+struct MinefieldRendererTextures {
+    /// Indexed by danger level.
+    tile_danger: Vec<Texture>,
+    /// Indexed by minus the danger level, minus one.
+    tile_negative_danger: Vec<Texture>,
+    /// Indexed by number of flagged mines, minus one.
+    tile_flag_mine: Vec<Texture>,
+    /// Indexed by number of flagged anti-mines, minus one.
+    tile_flag_anti_mine: Vec<Texture>,
+    tile_flag_question: Texture,
+    tile_flag_wrong: Texture,
+    /// Indexed by number of mines, minus one.
+    tile_mine: Vec<Texture>,
+    /// Indexed by number of anti-mines, minus one.
+    tile_anti_mine: Vec<Texture>,
+    tile_blank: Texture,
+    /// Heatmap percentages, indexed by percentage minus one, 1% to 99%.
+    tile_percent: Vec<Texture>,
+}
+
+impl MinefieldRendererTextures {
+    pub fn new<C>(
+        font: ttf::Font,
+        texture_creator: &TextureCreator<C>,
+        min_danger: i32,
+        max_danger: i32,
+    ) -> Result<MinefieldRendererTextures, Box<dyn Error>> {
+        let render = |text: &str, (r, g, b): (u8, u8, u8)| {
+            texture_creator
+                .create_texture_from_surface(
+                    font.render(text)
+                        .blended(Color::RGB(r, g, b))
+                        .map_err(|e| e.to_string())?,
+                )
+                .map_err(|e| e.to_string())
+        };
+
+        let tile_danger = (0..=max_danger)
+            .map(|level| {
+                let color = DANGER_COLORS[(level as usize).min(DANGER_COLORS.len() - 1)];
+                render(&level.to_string(), color)
+            })
+            .collect::<Result<Vec<Texture>, String>>()?;
+        let tile_negative_danger = (1..=-min_danger)
+            .map(|level| render(&format!("-{level}"), ANTI_COLOR))
+            .collect::<Result<Vec<Texture>, String>>()?;
+
+        // one more letter and a warmer colour for every mine on the tile
+        let tile_flag_mine = [
+            ("F", (0, 0, 0)),
+            ("FF", (150, 60, 0)),
+            ("FFF", (200, 0, 120)),
+        ]
+        .into_iter()
+        .map(|(glyph, color)| render(glyph, color))
+        .collect::<Result<Vec<Texture>, String>>()?;
+        let tile_flag_anti_mine = ["-F", "-FF", "-FFF"]
+            .into_iter()
+            .map(|glyph| render(glyph, ANTI_COLOR))
+            .collect::<Result<Vec<Texture>, String>>()?;
+
+        let tile_flag_question = texture_creator
+            .create_texture_from_surface(
+                font.render("?")
+                    .blended(Color::RGB(50, 50, 50))
+                    .map_err(|e| e.to_string())?,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let tile_flag_wrong = texture_creator
+            .create_texture_from_surface(
+                font.render("X")
+                    .blended(Color::RGB(202, 0, 0))
+                    .map_err(|e| e.to_string())?,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let tile_mine = [
+            ("*", (222, 13, 13)),
+            ("**", (160, 0, 60)),
+            ("***", (90, 0, 90)),
+        ]
+        .into_iter()
+        .map(|(glyph, color)| render(glyph, color))
+        .collect::<Result<Vec<Texture>, String>>()?;
+        let tile_anti_mine = ["-*", "-**", "-***"]
+            .into_iter()
+            .map(|glyph| render(glyph, ANTI_COLOR))
+            .collect::<Result<Vec<Texture>, String>>()?;
+
+        let tile_blank = texture_creator
+            .create_texture_from_surface(
+                font.render(" ")
+                    .blended(Color::RGB(0, 0, 0))
+                    .map_err(|e| e.to_string())?,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let tile_percent = (1..=99)
+            .map(|percent| render(&format!("{percent}%"), (60, 60, 60)))
+            .collect::<Result<Vec<Texture>, String>>()?;
+
+        Ok(MinefieldRendererTextures {
+            tile_danger,
+            tile_negative_danger,
+            tile_flag_mine,
+            tile_flag_anti_mine,
+            tile_flag_question,
+            tile_flag_wrong,
+            tile_mine,
+            tile_anti_mine,
+            tile_blank,
+            tile_percent,
+        })
+    }
+
+    fn danger(&self, level: i32) -> Option<&Texture> {
+        if level < 0 {
+            self.tile_negative_danger.get((-level - 1) as usize)
+        } else {
+            self.tile_danger.get(level as usize)
+        }
+    }
+
+    /// Texture of a tile holding mines, or anti-mines for a negative charge.
+    fn mine(&self, charge: i32) -> &Texture {
+        let textures = if charge < 0 {
+            &self.tile_anti_mine
+        } else {
+            &self.tile_mine
+        };
+        by_count(textures, charge)
+    }
+
+    /// Texture of a chance that is not certain, proven tiles have no text.
+    fn percent(&self, probability: f64) -> Option<&Texture> {
+        if probability <= 0.0 || probability >= 1.0 {
+            return None;
+        }
+        // never round to 0% or 100%, that would claim a proof
+        let percent = ((probability * 100.0).round() as usize).clamp(1, 99);
+        self.tile_percent.get(percent - 1)
+    }
+
+    fn flag(&self, charge: i32) -> &Texture {
+        let textures = if charge < 0 {
+            &self.tile_flag_anti_mine
+        } else {
+            &self.tile_flag_mine
+        };
+        by_count(textures, charge)
+    }
+}
+
+/// Texture of a number of mines, the last one is reused past the end.
+fn by_count(textures: &[Texture], charge: i32) -> &Texture {
+    let index = (charge.unsigned_abs().max(1) as usize - 1).min(textures.len() - 1);
+    &textures[index]
+}
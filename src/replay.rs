@@ -0,0 +1,337 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::minefield::Minefield;
+
+const REPLAY_HEADER: &str = "mines replay";
+const REPLAY_VERSION: u32 = 1;
+
+/// An input applied to the board by the game loop.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Move {
+    Uncover(usize),
+    Flag(usize),
+    /// A fresh board, with the seed it was generated from.
+    Reset(u64),
+    Restart,
+    Undo,
+    Redo,
+}
+
+impl Move {
+    pub fn apply(&self, minefield: &mut Minefield) {
+        match *self {
+            Move::Uncover(tile_number) => minefield.uncover_tile(tile_number),
+            Move::Flag(tile_number) => minefield.flag_tile(tile_number),
+            Move::Reset(seed) => minefield.reset_with_seed(seed),
+            Move::Restart => minefield.restart(),
+            Move::Undo => {
+                minefield.undo();
+            }
+            Move::Redo => {
+                minefield.redo();
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayEvent {
+    /// Time since the recording started.
+    pub time: Duration,
+    pub action: Move,
+}
+
+/// Starting board of a game and every move played on it.
+pub struct Replay {
+    board: String,
+    events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new(minefield: &Minefield) -> Replay {
+        Replay {
+            board: minefield.to_save_string(),
+            events: vec![],
+        }
+    }
+
+    pub fn push(&mut self, time: Duration, action: Move) {
+        self.events.push(ReplayEvent { time, action });
+    }
+
+    pub fn events(&self) -> &[ReplayEvent] {
+        &self.events
+    }
+
+    /// A copy of the board as it was when the recording started.
+    pub fn board(&self) -> Result<Minefield, String> {
+        Minefield::from_save_string(&self.board)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_replay_string())
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Replay::from_replay_string(&contents)
+    }
+
+    pub fn to_replay_string(&self) -> String {
+        let mut out = format!("{REPLAY_HEADER} {REPLAY_VERSION}\n");
+
+        // one move per line, prefixed by its time in milliseconds
+        for event in self.events.iter() {
+            let action = match event.action {
+                Move::Uncover(tile_number) => format!("uncover {tile_number}"),
+                Move::Flag(tile_number) => format!("flag {tile_number}"),
+                Move::Reset(seed) => format!("reset {seed}"),
+                Move::Restart => "restart".to_string(),
+                Move::Undo => "undo".to_string(),
+                Move::Redo => "redo".to_string(),
+            };
+            out += &format!("{} {action}\n", event.time.as_millis());
+        }
+
+        // the starting board follows in the save file format
+        out += "board\n";
+        out += &self.board;
+
+        out
+    }
+
+    pub fn from_replay_string(contents: &str) -> Result<Replay, String> {
+        let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        match lines.next() {
+            Some((_, line)) if line == format!("{REPLAY_HEADER} {REPLAY_VERSION}") => {}
+            Some((_, line)) if line.starts_with(REPLAY_HEADER) => {
+                return Err(format!("unsupported replay version: {line}"));
+            }
+            _ => return Err("not a mines replay file".to_string()),
+        }
+
+        let mut events = vec![];
+        // line and tile of every move on a tile, checked once the board is known
+        let mut tile_moves = vec![];
+        for (number, line) in lines.by_ref() {
+            if line == "board" {
+                let board: Vec<&str> = contents.lines().skip(number).collect();
+                let replay = Replay {
+                    board: board.join("\n") + "\n",
+                    events,
+                };
+                // fail early on a broken board or move rather than when playing it
+                let total_tiles = replay.board()?.total_tiles();
+                for (number, tile_number) in tile_moves {
+                    if tile_number >= total_tiles {
+                        return Err(format!(
+                            "line {number}: tile {tile_number} is not on the board, it has {total_tiles} tiles"
+                        ));
+                    }
+                }
+                return Ok(replay);
+            }
+
+            let invalid = || format!("line {number}: invalid move '{line}'");
+            let mut words = line.split_whitespace();
+            let time: u64 = words
+                .next()
+                .and_then(|time| time.parse().ok())
+                .ok_or_else(invalid)?;
+            let name = words.next();
+            let argument = words
+                .next()
+                .and_then(|argument| argument.parse::<u64>().ok());
+            let action = match (name, argument) {
+                (Some("uncover"), Some(tile_number)) => Move::Uncover(tile_number as usize),
+                (Some("flag"), Some(tile_number)) => Move::Flag(tile_number as usize),
+                (Some("reset"), Some(seed)) => Move::Reset(seed),
+                (Some("restart"), None) => Move::Restart,
+                (Some("undo"), None) => Move::Undo,
+                (Some("redo"), None) => Move::Redo,
+                _ => return Err(invalid()),
+            };
+            if let Move::Uncover(tile_number) | Move::Flag(tile_number) = action {
+                tile_moves.push((number, tile_number));
+            }
+            events.push(ReplayEvent {
+                time: Duration::from_millis(time),
+                action,
+            });
+        }
+
+        Err("replay has no board".to_string())
+    }
+}
+
+/// Records the moves of a game as they are played.
+pub struct Recorder {
+    replay: Replay,
+    started_at: Instant,
+}
+
+impl Recorder {
+    pub fn new(minefield: &Minefield) -> Recorder {
+        Recorder {
+            replay: Replay::new(minefield),
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, action: Move) {
+        self.replay.push(self.started_at.elapsed(), action);
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackSpeed {
+    /// Plays the moves at their recorded times scaled by the factor, 1 is real time.
+    Factor(f32),
+    /// Moves are only played one at a time with `Player::step`.
+    Step,
+}
+
+impl FromStr for PlaybackSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PlaybackSpeed, String> {
+        match s {
+            "step" => Ok(PlaybackSpeed::Step),
+            _ => match s.parse::<f32>() {
+                Ok(factor) if factor > 0.0 => Ok(PlaybackSpeed::Factor(factor)),
+                _ => Err(format!("invalid playback speed '{s}'")),
+            },
+        }
+    }
+}
+
+/// Drives a board through the moves of a replay.
+pub struct Player {
+    replay: Replay,
+    speed: PlaybackSpeed,
+    next_event: usize,
+    started_at: Instant,
+}
+
+impl Player {
+    pub fn new(replay: Replay, speed: PlaybackSpeed) -> Player {
+        Player {
+            replay,
+            speed,
+            next_event: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_event >= self.replay.events.len()
+    }
+
+    /// Plays the moves that are due at the current playback speed.
+    pub fn advance(&mut self, minefield: &mut Minefield) -> bool {
+        match self.speed {
+            PlaybackSpeed::Factor(factor) => {
+                let replay_time = self.started_at.elapsed().mul_f32(factor);
+                self.advance_to(minefield, replay_time)
+            }
+            _ => false,
+        }
+    }
+
+    /// Plays every move recorded up to `time`, returns true if any was played.
+    pub fn advance_to(&mut self, minefield: &mut Minefield, time: Duration) -> bool {
+        let first_event = self.next_event;
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.time > time {
+                break;
+            }
+            event.action.apply(minefield);
+            self.next_event += 1;
+        }
+        self.next_event > first_event
+    }
+
+    /// Plays the next move, returns false once the replay is over.
+    pub fn step(&mut self, minefield: &mut Minefield) -> bool {
+        match self.replay.events.get(self.next_event) {
+            Some(event) => {
+                event.action.apply(minefield);
+                self.next_event += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minefield::FirstClick;
+
+    fn replay() -> Replay {
+        let minefield = Minefield::with_mine_count(4, 4, 3, FirstClick::Safe, 7);
+        let mut replay = Replay::new(&minefield);
+        replay.push(Duration::from_millis(100), Move::Uncover(5));
+        replay.push(Duration::from_millis(300), Move::Flag(15));
+        replay.push(Duration::from_millis(300), Move::Undo);
+        replay.push(Duration::from_millis(450), Move::Redo);
+        replay.push(Duration::from_millis(600), Move::Restart);
+        replay.push(Duration::from_millis(900), Move::Reset(42));
+        replay
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        let contents = replay.to_replay_string();
+        let loaded = Replay::from_replay_string(&contents).unwrap();
+        assert_eq!(loaded.events(), replay.events());
+        assert_eq!(loaded.to_replay_string(), contents);
+        assert_eq!(
+            loaded.board().unwrap().to_save_string(),
+            replay.board().unwrap().to_save_string()
+        );
+    }
+
+    #[test]
+    fn rejects_tiles_off_the_board() {
+        let contents = replay()
+            .to_replay_string()
+            .replace("300 flag 15", "300 flag 16");
+        assert_eq!(
+            Replay::from_replay_string(&contents).err(),
+            Some("line 3: tile 16 is not on the board, it has 16 tiles".to_string())
+        );
+    }
+
+    #[test]
+    fn advance_to() {
+        let replay = replay();
+        let mut minefield = replay.board().unwrap();
+        let mut player = Player::new(replay, PlaybackSpeed::Step);
+
+        assert!(!player.advance_to(&mut minefield, Duration::from_millis(50)));
+        assert!(minefield.tile_is_hidden(5));
+        assert!(player.advance_to(&mut minefield, Duration::from_millis(100)));
+        assert!(!minefield.tile_is_hidden(5));
+        assert!(!player.advance_to(&mut minefield, Duration::from_millis(200)));
+        // moves sharing a time are played together
+        assert!(player.advance_to(&mut minefield, Duration::from_millis(300)));
+        assert_eq!(minefield.get_tile_flag(15), None);
+        assert!(player.advance_to(&mut minefield, Duration::from_secs(1)));
+        assert!(player.is_finished());
+        assert!(!player.advance_to(&mut minefield, Duration::from_secs(2)));
+        assert_eq!(minefield.seed(), 42);
+    }
+}
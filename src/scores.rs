@@ -0,0 +1,401 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::minefield::{FirstClick, Minefield};
+use crate::stats::Stats;
+
+const SCORES_HEADER: &str = "mines scores";
+const SCORES_VERSION: u32 = 2;
+
+/// How many of the best games are kept for each board.
+pub const MAX_SCORES_PER_BOARD: usize = 10;
+
+/// Size, mine count and first click mode of a board, scores are only compared
+/// within one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BoardKey {
+    pub rows: usize,
+    pub cols: usize,
+    pub mines: usize,
+    pub first_click: FirstClick,
+}
+
+impl BoardKey {
+    pub fn new(minefield: &Minefield) -> BoardKey {
+        BoardKey {
+            rows: minefield.rows(),
+            cols: minefield.cols(),
+            mines: minefield.mine_count(),
+            first_click: minefield.first_click(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub board: BoardKey,
+    pub time: Duration,
+    pub three_bv: usize,
+    pub clicks: u32,
+    /// Seconds since the Unix epoch.
+    pub date: u64,
+    pub name: String,
+}
+
+impl Score {
+    /// Score of a won game, dated now.
+    pub fn new(minefield: &Minefield, name: &str) -> Score {
+        let stats = Stats::new(minefield);
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+        Score {
+            board: BoardKey::new(minefield),
+            time: stats.elapsed,
+            three_bv: stats.three_bv,
+            clicks: stats.clicks.total(),
+            date,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.time.is_zero() {
+            return 0.0;
+        }
+        self.three_bv as f64 / self.time.as_secs_f64()
+    }
+
+    pub fn ioe(&self) -> f64 {
+        if self.clicks == 0 {
+            return 0.0;
+        }
+        self.three_bv as f64 / self.clicks as f64
+    }
+}
+
+/// Best times for every board played, kept in a local data file.
+#[derive(Debug, Default)]
+pub struct HighScores {
+    scores: Vec<Score>,
+}
+
+impl HighScores {
+    /// Reads the tables, a missing file is an empty table.
+    pub fn load(path: &Path) -> Result<HighScores, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => HighScores::from_scores_string(&contents),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(e) => Err(format!("cannot read {}: {e}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_scores_string())
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))
+    }
+
+    /// Adds a score, returns true if it is the best time on its board.
+    pub fn add(&mut self, score: Score) -> bool {
+        let board = score.board;
+        let is_record = self
+            .table(board)
+            .first()
+            .is_none_or(|best| score.time < best.time);
+
+        self.scores.push(score);
+        self.scores
+            .sort_by(|a, b| a.board.cmp(&b.board).then(a.time.cmp(&b.time)));
+
+        // only keep the best games of the board
+        let mut kept = 0;
+        self.scores.retain(|score| {
+            if score.board != board {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_SCORES_PER_BOARD
+        });
+
+        is_record
+    }
+
+    pub fn clear(&mut self) {
+        self.scores.clear();
+    }
+
+    pub fn boards(&self) -> Vec<BoardKey> {
+        let mut boards: Vec<BoardKey> = self.scores.iter().map(|score| score.board).collect();
+        boards.sort();
+        boards.dedup();
+        boards
+    }
+
+    /// Scores of one board, best time first.
+    pub fn table(&self, board: BoardKey) -> Vec<&Score> {
+        let mut table: Vec<&Score> = self
+            .scores
+            .iter()
+            .filter(|score| score.board == board)
+            .collect();
+        table.sort_by_key(|score| score.time);
+        table
+    }
+
+    pub fn to_scores_string(&self) -> String {
+        let mut out = format!("{SCORES_HEADER} {SCORES_VERSION}\n");
+
+        // rows cols mines first_click time_ms 3bv clicks date name, the name takes
+        // the rest of the line
+        for score in self.scores.iter() {
+            out += &format!(
+                "{} {} {} {} {} {} {} {} {}\n",
+                score.board.rows,
+                score.board.cols,
+                score.board.mines,
+                score.board.first_click,
+                score.time.as_millis(),
+                score.three_bv,
+                score.clicks,
+                score.date,
+                score.name
+            );
+        }
+
+        out
+    }
+
+    pub fn from_scores_string(contents: &str) -> Result<HighScores, String> {
+        let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        let version = match lines.next() {
+            Some((_, line)) if line.starts_with(SCORES_HEADER) => line[SCORES_HEADER.len()..]
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|version| (1..=SCORES_VERSION).contains(version))
+                .ok_or_else(|| format!("unsupported scores version: {line}"))?,
+            _ => return Err("not a mines scores file".to_string()),
+        };
+
+        // version 1 had no first click mode, those games were played with the default
+        let field_count = if version >= 2 { 9 } else { 8 };
+        let mut scores = vec![];
+        for (number, line) in lines {
+            let invalid = || format!("line {number}: invalid score '{line}'");
+            let mut fields: Vec<&str> = line.splitn(field_count, ' ').collect();
+            if fields.len() != field_count {
+                return Err(invalid());
+            }
+            let first_click = if version >= 2 {
+                fields.remove(3).parse().map_err(|_| invalid())?
+            } else {
+                FirstClick::Safe
+            };
+            let number_at = |i: usize| fields[i].parse::<u64>().map_err(|_| invalid());
+            scores.push(Score {
+                board: BoardKey {
+                    rows: number_at(0)? as usize,
+                    cols: number_at(1)? as usize,
+                    mines: number_at(2)? as usize,
+                    first_click,
+                },
+                time: Duration::from_millis(number_at(3)?),
+                three_bv: number_at(4)? as usize,
+                clicks: number_at(5)? as u32,
+                date: number_at(6)?,
+                name: fields[7].to_string(),
+            });
+        }
+
+        Ok(HighScores { scores })
+    }
+}
+
+/// Formats seconds since the Unix epoch as a `YYYY-MM-DD` UTC date.
+pub fn format_date(date: u64) -> String {
+    // days to civil date, after Howard Hinnant's algorithm
+    let days = (date / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Prints every table of the file, one board after the other.
+pub fn print_tables(high_scores: &HighScores) {
+    let boards = high_scores.boards();
+    if boards.is_empty() {
+        println!("No scores yet");
+    }
+
+    for board in boards {
+        println!(
+            "{} x {}, {} mines, {} first click",
+            board.rows, board.cols, board.mines, board.first_click
+        );
+        for (rank, score) in high_scores.table(board).iter().enumerate() {
+            println!(
+                "{:>3}. {:>8.2}s  3BV/s {:>5.2}  IOE {:>4.2}  {}  {}",
+                rank + 1,
+                score.time.as_secs_f64(),
+                score.three_bv_per_second(),
+                score.ioe(),
+                format_date(score.date),
+                score.name
+            );
+        }
+    }
+}
+
+/// Lists or clears the tables of the scores file, as asked on the command line.
+pub fn run(config: &Config) -> Result<(), String> {
+    let path = Path::new(&config.scores_file);
+    let mut high_scores = HighScores::load(path)?;
+
+    if config.clear_scores {
+        high_scores.clear();
+        high_scores.save(path)?;
+        println!("High scores cleared");
+    } else {
+        print_tables(&high_scores);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: BoardKey = BoardKey {
+        rows: 9,
+        cols: 9,
+        mines: 10,
+        first_click: FirstClick::Safe,
+    };
+
+    fn score(board: BoardKey, time_ms: u64, name: &str) -> Score {
+        Score {
+            board,
+            time: Duration::from_millis(time_ms),
+            three_bv: 20,
+            clicks: 30,
+            date: 1_700_000_000,
+            name: name.to_string(),
+        }
+    }
+
+    fn names(high_scores: &HighScores, board: BoardKey) -> Vec<&str> {
+        high_scores
+            .table(board)
+            .iter()
+            .map(|score| score.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_best_ten() {
+        let other = BoardKey {
+            first_click: FirstClick::NoGuess,
+            ..BOARD
+        };
+        let mut high_scores = HighScores::default();
+        high_scores.add(score(other, 50_000, "other"));
+        for i in 1..=MAX_SCORES_PER_BOARD as u64 {
+            high_scores.add(score(BOARD, 10_000 + i * 1000, &format!("p{i}")));
+        }
+        assert!(!high_scores.add(score(BOARD, 30_000, "slow")));
+        assert!(!high_scores.add(score(BOARD, 15_500, "middle")));
+        assert!(high_scores.add(score(BOARD, 9_000, "best")));
+
+        assert_eq!(
+            names(&high_scores, BOARD),
+            ["best", "p1", "p2", "p3", "p4", "p5", "middle", "p6", "p7", "p8"]
+        );
+        // other boards keep their own table
+        assert_eq!(names(&high_scores, other), ["other"]);
+    }
+
+    #[test]
+    fn equal_times_keep_the_first() {
+        let mut high_scores = HighScores::default();
+        assert!(high_scores.add(score(BOARD, 12_000, "first")));
+        assert!(!high_scores.add(score(BOARD, 12_000, "second")));
+        high_scores.add(score(BOARD, 11_000, "faster"));
+        assert!(!high_scores.add(score(BOARD, 12_000, "third")));
+        assert_eq!(
+            names(&high_scores, BOARD),
+            ["faster", "first", "second", "third"]
+        );
+
+        // a full table of equal times drops the newest
+        for i in 0..MAX_SCORES_PER_BOARD {
+            high_scores.add(score(BOARD, 12_000, &format!("late{i}")));
+        }
+        let table = names(&high_scores, BOARD);
+        assert_eq!(table.len(), MAX_SCORES_PER_BOARD);
+        assert_eq!(table[..4], ["faster", "first", "second", "third"]);
+        assert_eq!(table[9], "late5");
+
+        let loaded = HighScores::from_scores_string(&high_scores.to_scores_string()).unwrap();
+        assert_eq!(names(&loaded, BOARD), table);
+    }
+
+    #[test]
+    fn loads_version_1() {
+        let contents = "mines scores 1
+9 9 10 15300 25 40 1700000000 Ada
+16 16 40 61000 120 180 1700086400 Two words
+";
+        let high_scores = HighScores::from_scores_string(contents).unwrap();
+        let ada = Score {
+            three_bv: 25,
+            clicks: 40,
+            ..score(BOARD, 15_300, "Ada")
+        };
+        assert_eq!(high_scores.table(BOARD), [&ada]);
+        let expert = BoardKey {
+            rows: 16,
+            cols: 16,
+            mines: 40,
+            first_click: FirstClick::Safe,
+        };
+        let table = high_scores.table(expert);
+        assert_eq!(table.len(), 1);
+        assert_eq!(table[0].name, "Two words");
+        assert_eq!(table[0].date, 1_700_086_400);
+
+        // saved again in the current version
+        let resaved = high_scores.to_scores_string();
+        assert!(resaved.starts_with(&format!("{SCORES_HEADER} {SCORES_VERSION}\n")));
+        assert!(resaved.contains("\n9 9 10 safe 15300 25 40 1700000000 Ada\n"));
+        let loaded = HighScores::from_scores_string(&resaved).unwrap();
+        assert_eq!(loaded.to_scores_string(), resaved);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let error = HighScores::from_scores_string("mines scores 3\n").err();
+        assert_eq!(
+            error.as_deref(),
+            Some("unsupported scores version: mines scores 3")
+        );
+        let error = HighScores::from_scores_string("mines save 1\n").err();
+        assert_eq!(error.as_deref(), Some("not a mines scores file"));
+    }
+}
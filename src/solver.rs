@@ -0,0 +1,638 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::minefield::{MineDistribution, Minefield, TileContent};
+
+/// Tiles proven safe or proven to be mines from the visible board.
+#[derive(Debug, Default, PartialEq)]
+pub struct Deductions {
+    pub safe: Vec<usize>,
+    pub mines: Vec<usize>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// A revealed number: exactly `mines` of the unknown `tiles` are mines.
+#[derive(Debug, PartialEq)]
+struct Constraint {
+    tiles: Vec<usize>,
+    mines: i32,
+}
+
+fn constraints(
+    minefield: &Minefield,
+    known_mines: &HashSet<usize>,
+    known_safe: &HashSet<usize>,
+) -> Vec<Constraint> {
+    let mut constraints = vec![];
+
+    for i in minefield.existing_tiles() {
+        if minefield.tile_is_hidden(i) {
+            continue;
+        }
+        let danger_level = match minefield.get_tile_content(i) {
+            TileContent::Danger(danger_level) => danger_level,
+            TileContent::Mine(_) | TileContent::AntiMine(_) => continue,
+        };
+
+        let mut tiles = vec![];
+        let mut mines = danger_level;
+        for (x, y) in minefield.get_neighbours(i).iter() {
+            let neighbour_index = minefield.indices_to_tile(*x, *y);
+            if !minefield.tile_is_hidden(neighbour_index) || known_safe.contains(&neighbour_index) {
+                continue;
+            }
+            if known_mines.contains(&neighbour_index) {
+                mines -= 1;
+            } else {
+                tiles.push(neighbour_index);
+            }
+        }
+
+        if tiles.is_empty() {
+            continue;
+        }
+        tiles.sort_unstable();
+        let constraint = Constraint { tiles, mines };
+        if !constraints.contains(&constraint) {
+            constraints.push(constraint);
+        }
+    }
+
+    constraints
+}
+
+/// Deduces safe tiles and mines from the revealed numbers, without guessing.
+///
+/// Only uncovered numbers, the mine count and the given `known_mines` are used,
+/// the hidden content of the board is never looked at. When tiles can hold
+/// several mines, only safe tiles are deduced: a number no longer tells how
+/// many tiles are mined. With anti-mines nothing is: mines can cancel out, even
+/// a zero proves nothing.
+pub fn deduce(minefield: &Minefield, known_mines: &HashSet<usize>) -> Deductions {
+    if minefield.mine_distribution().has_anti_mines() {
+        return Deductions::default();
+    }
+    let finds_mines = !minefield.mine_distribution().allows_multiple();
+    let mut safe = HashSet::new();
+    let mut mines = HashSet::new();
+
+    // every round works on what the previous ones proved, until nothing new is found
+    loop {
+        let known: HashSet<usize> = known_mines.union(&mines).copied().collect();
+        let constraints = constraints(minefield, &known, &safe);
+        let mut found_safe = HashSet::new();
+        let mut found_mines = HashSet::new();
+
+        // single tile rules
+        for constraint in constraints.iter() {
+            if constraint.mines == 0 {
+                found_safe.extend(constraint.tiles.iter());
+            } else if finds_mines && constraint.mines == constraint.tiles.len() as i32 {
+                found_mines.extend(constraint.tiles.iter());
+            }
+        }
+
+        for a in constraints.iter() {
+            for b in constraints.iter() {
+                if !a.tiles.iter().any(|tile| b.tiles.contains(tile)) || a == b {
+                    continue;
+                }
+                let only_a: Vec<usize> = a
+                    .tiles
+                    .iter()
+                    .filter(|tile| !b.tiles.contains(tile))
+                    .copied()
+                    .collect();
+                let only_b: Vec<usize> = b
+                    .tiles
+                    .iter()
+                    .filter(|tile| !a.tiles.contains(tile))
+                    .copied()
+                    .collect();
+
+                // subset rules: the tiles only in the bigger constraint hold the difference
+                if only_b.is_empty() {
+                    let rest_mines = a.mines - b.mines;
+                    if rest_mines == 0 {
+                        found_safe.extend(only_a.iter());
+                    } else if finds_mines && rest_mines == only_a.len() as i32 {
+                        found_mines.extend(only_a.iter());
+                    }
+                }
+
+                // overlap rules: when the shared tiles must hold all the mines of
+                // `b`, the tiles only in `a` are mines and the ones only in `b` safe
+                if finds_mines && a.mines - only_a.len() as i32 == b.mines {
+                    found_mines.extend(only_a.iter());
+                    found_safe.extend(only_b.iter());
+                }
+            }
+        }
+
+        // mine count rule: numbers that share no tile hold an exact number of
+        // mines between them, the remaining mines are in the other hidden tiles
+        let mut covered = HashSet::new();
+        let mut covered_mines = 0;
+        for constraint in constraints.iter() {
+            if constraint.tiles.iter().all(|tile| !covered.contains(tile)) {
+                covered.extend(constraint.tiles.iter().copied());
+                covered_mines += constraint.mines;
+            }
+        }
+        let rest: Vec<usize> = minefield
+            .existing_tiles()
+            .filter(|i| {
+                minefield.tile_is_hidden(*i)
+                    && !known.contains(i)
+                    && !safe.contains(i)
+                    && !covered.contains(i)
+            })
+            .collect();
+        let rest_mines = minefield.mine_count() as i32 - known.len() as i32 - covered_mines;
+        if rest_mines == 0 {
+            found_safe.extend(rest.iter());
+        } else if finds_mines && rest_mines == rest.len() as i32 {
+            found_mines.extend(rest.iter());
+        }
+
+        found_safe.retain(|tile| !safe.contains(tile));
+        found_mines.retain(|tile| !mines.contains(tile));
+        if found_safe.is_empty() && found_mines.is_empty() {
+            break;
+        }
+        safe.extend(found_safe);
+        mines.extend(found_mines);
+    }
+
+    let mut deductions = Deductions {
+        safe: safe.into_iter().collect(),
+        mines: mines.into_iter().collect(),
+    };
+    deductions.safe.sort_unstable();
+    deductions.mines.sort_unstable();
+    deductions
+}
+
+/// Deduces what the player can see: uncovered numbers and the mine count.
+/// Flags are not trusted, a wrong one would lead to wrong deductions.
+pub fn solve(minefield: &Minefield) -> Deductions {
+    deduce(minefield, &HashSet::new())
+}
+
+/// Chance of every hidden tile to be a mine, from the uncovered numbers and
+/// the mine count. Flags are not trusted. Proven tiles get exactly 0 or 1.
+///
+/// Hidden tiles next to numbers are enumerated, in groups that share no
+/// number, and the other hidden tiles weigh in by the number of ways the
+/// remaining mines spread over them. Wide frontiers with few numbers are slow.
+pub fn probabilities(minefield: &Minefield) -> Result<HashMap<usize, f64>, String> {
+    if minefield.mine_distribution() != MineDistribution::default() {
+        return Err("probabilities need boards with single mines only".to_string());
+    }
+    let deductions = deduce(minefield, &HashSet::new());
+    let known_mines: HashSet<usize> = deductions.mines.iter().copied().collect();
+    let known_safe: HashSet<usize> = deductions.safe.iter().copied().collect();
+    let constraints = constraints(minefield, &known_mines, &known_safe);
+
+    let mut probabilities = HashMap::new();
+    probabilities.extend(known_safe.iter().map(|tile| (*tile, 0.0)));
+    probabilities.extend(known_mines.iter().map(|tile| (*tile, 1.0)));
+
+    let groups: Vec<Group> = split_groups(&constraints)
+        .iter()
+        .map(|group| Group::enumerate(group))
+        .collect();
+    let interior: Vec<usize> = minefield
+        .existing_tiles()
+        .filter(|i| {
+            minefield.tile_is_hidden(*i)
+                && !probabilities.contains_key(i)
+                && !groups.iter().any(|group| group.tiles.contains(i))
+        })
+        .collect();
+    let remaining_mines = minefield.mine_count() as i64 - known_mines.len() as i64;
+
+    // weights[m]: ways to lay the other mines in the interior when the
+    // frontier holds m, scaled so that the biggest one is 1
+    let frontier_size: usize = groups.iter().map(|group| group.tiles.len()).sum();
+    let log_weights: Vec<f64> = (0..=frontier_size as i64)
+        .map(|m| ln_choose(interior.len() as i64, remaining_mines - m))
+        .collect();
+    let max_log_weight = log_weights
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    if max_log_weight == f64::NEG_INFINITY {
+        return Err("the numbers do not match the mine count".to_string());
+    }
+    let weights: Vec<f64> = log_weights
+        .iter()
+        .map(|log_weight| (log_weight - max_log_weight).exp())
+        .collect();
+
+    let all_ways = groups
+        .iter()
+        .fold(vec![1.0], |ways, group| convolve(&ways, &group.ways));
+    let total: f64 = all_ways
+        .iter()
+        .zip(weights.iter())
+        .map(|(ways, weight)| ways * weight)
+        .sum();
+    if total == 0.0 {
+        return Err("the numbers do not match the mine count".to_string());
+    }
+
+    for (g, group) in groups.iter().enumerate() {
+        let others = groups
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != g)
+            .fold(vec![1.0], |ways, (_, other)| convolve(&ways, &other.ways));
+        // weight of this group holding k mines, whatever the others hold
+        let group_weights: Vec<f64> = (0..group.ways.len())
+            .map(|k| {
+                others
+                    .iter()
+                    .enumerate()
+                    .map(|(m, ways)| ways * weights[k + m])
+                    .sum()
+            })
+            .collect();
+        for (tile, mine_ways) in group.tiles.iter().zip(group.mine_ways.iter()) {
+            let mine_weight: f64 = mine_ways
+                .iter()
+                .zip(group_weights.iter())
+                .map(|(ways, weight)| ways * weight)
+                .sum();
+            probabilities.insert(*tile, mine_weight / total);
+        }
+    }
+
+    if !interior.is_empty() {
+        let interior_mines: f64 = all_ways
+            .iter()
+            .zip(weights.iter())
+            .enumerate()
+            .map(|(m, (ways, weight))| ways * weight * (remaining_mines - m as i64).max(0) as f64)
+            .sum();
+        let probability = interior_mines / total / interior.len() as f64;
+        probabilities.extend(interior.iter().map(|tile| (*tile, probability)));
+    }
+
+    Ok(probabilities)
+}
+
+/// Hidden tiles tied together by numbers, with how many of their layouts
+/// hold each number of mines.
+struct Group {
+    tiles: Vec<usize>,
+    /// `ways[k]`: layouts with k mines, scaled so that the biggest is 1.
+    ways: Vec<f64>,
+    /// `mine_ways[t][k]`: layouts with k mines where `tiles[t]` is a mine.
+    mine_ways: Vec<Vec<f64>>,
+}
+
+impl Group {
+    fn enumerate(constraints: &[&Constraint]) -> Group {
+        // tiles in the order the numbers come, so that they are checked early
+        let mut tiles: Vec<usize> = vec![];
+        for constraint in constraints.iter() {
+            for tile in constraint.tiles.iter() {
+                if !tiles.contains(tile) {
+                    tiles.push(*tile);
+                }
+            }
+        }
+        let tile_constraints: Vec<Vec<usize>> = tiles
+            .iter()
+            .map(|tile| {
+                (0..constraints.len())
+                    .filter(|c| constraints[*c].tiles.contains(tile))
+                    .collect()
+            })
+            .collect();
+
+        let mut search = Search {
+            constraints,
+            tile_constraints: &tile_constraints,
+            is_mine: vec![false; tiles.len()],
+            mines: vec![0; constraints.len()],
+            unknown: constraints.iter().map(|c| c.tiles.len() as i32).collect(),
+            ways: vec![0.0; tiles.len() + 1],
+            mine_ways: vec![vec![0.0; tiles.len() + 1]; tiles.len()],
+        };
+        search.assign(0);
+
+        let scale = search.ways.iter().copied().fold(0.0, f64::max).max(1.0);
+        Group {
+            tiles,
+            ways: search.ways.iter().map(|ways| ways / scale).collect(),
+            mine_ways: search
+                .mine_ways
+                .iter()
+                .map(|mine_ways| mine_ways.iter().map(|ways| ways / scale).collect())
+                .collect(),
+        }
+    }
+}
+
+/// Backtracking over the mines of a group, one tile after the other.
+struct Search<'a> {
+    constraints: &'a [&'a Constraint],
+    tile_constraints: &'a [Vec<usize>],
+    is_mine: Vec<bool>,
+    /// Mines and undecided tiles of every constraint so far.
+    mines: Vec<i32>,
+    unknown: Vec<i32>,
+    ways: Vec<f64>,
+    mine_ways: Vec<Vec<f64>>,
+}
+
+impl Search<'_> {
+    fn assign(&mut self, tile: usize) {
+        if tile == self.is_mine.len() {
+            let mines = self.is_mine.iter().filter(|is_mine| **is_mine).count();
+            self.ways[mines] += 1.0;
+            for (t, is_mine) in self.is_mine.iter().enumerate() {
+                if *is_mine {
+                    self.mine_ways[t][mines] += 1.0;
+                }
+            }
+            return;
+        }
+
+        for is_mine in [false, true] {
+            self.is_mine[tile] = is_mine;
+            for c in self.tile_constraints[tile].iter() {
+                self.unknown[*c] -= 1;
+                self.mines[*c] += is_mine as i32;
+            }
+            let possible = self.tile_constraints[tile].iter().all(|c| {
+                let wanted = self.constraints[*c].mines;
+                self.mines[*c] <= wanted && self.mines[*c] + self.unknown[*c] >= wanted
+            });
+            if possible {
+                self.assign(tile + 1);
+            }
+            for c in self.tile_constraints[tile].iter() {
+                self.unknown[*c] += 1;
+                self.mines[*c] -= is_mine as i32;
+            }
+        }
+        self.is_mine[tile] = false;
+    }
+}
+
+/// Splits the constraints into groups that share no tile.
+fn split_groups(constraints: &[Constraint]) -> Vec<Vec<&Constraint>> {
+    let mut grouped = vec![false; constraints.len()];
+    let mut groups = vec![];
+
+    for start in 0..constraints.len() {
+        if grouped[start] {
+            continue;
+        }
+        grouped[start] = true;
+        let mut group = vec![&constraints[start]];
+        let mut next = 0;
+        while next < group.len() {
+            let current = group[next];
+            for (c, constraint) in constraints.iter().enumerate() {
+                if !grouped[c]
+                    && constraint
+                        .tiles
+                        .iter()
+                        .any(|tile| current.tiles.contains(tile))
+                {
+                    grouped[c] = true;
+                    group.push(constraint);
+                }
+            }
+            next += 1;
+        }
+        groups.push(group);
+    }
+
+    groups
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// Logarithm of the number of ways to pick `k` out of `n`.
+fn ln_choose(n: i64, k: i64) -> f64 {
+    if k < 0 || k > n {
+        return f64::NEG_INFINITY;
+    }
+    let k = k.min(n - k);
+    (0..k).map(|i| ((n - i) as f64 / (i + 1) as f64).ln()).sum()
+}
+
+/// Plays a copy of the board from `first_tile` using deductions only and
+/// reports whether every safe tile could be uncovered.
+pub fn is_solvable(minefield: &Minefield, first_tile: usize) -> bool {
+    let mut board = minefield.clone();
+    board.set_history_enabled(false);
+    let mut known_mines = HashSet::new();
+
+    board.uncover_tile(first_tile);
+    loop {
+        let deductions = deduce(&board, &known_mines);
+        if deductions.is_empty() {
+            break;
+        }
+        known_mines.extend(deductions.mines);
+        for i in deductions.safe {
+            if board.tile_is_hidden(i) {
+                board.uncover_tile(i);
+            }
+        }
+    }
+
+    let cleared = board
+        .existing_tiles()
+        .all(|i| !board.tile_is_hidden(i) || board.mines_on_tile(i) > 0);
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minefield::FirstClick;
+
+    fn deduce_layout(layout: &str) -> Deductions {
+        deduce(
+            &Minefield::from_layout_str(layout).unwrap(),
+            &HashSet::new(),
+        )
+    }
+
+    #[test]
+    fn single_tile_rules() {
+        // a 2 next to two hidden tiles, a 0 next to two others
+        let deductions = deduce_layout("*o*.o..*\n");
+        assert_eq!(deductions.mines, vec![0, 2]);
+        assert_eq!(deductions.safe, vec![3, 5]);
+    }
+
+    #[test]
+    fn one_two_one() {
+        let deductions = deduce_layout("*.*\nooo\nooo\n");
+        assert_eq!(deductions.mines, vec![0, 6]);
+        assert_eq!(deductions.safe, vec![3]);
+    }
+
+    #[test]
+    fn overlap_rule() {
+        // the 3 needs both tiles the 1 cannot see, the 1 gets its mine from the shared ones
+        let deductions = deduce_layout("**...*\n*oo...\n");
+        assert_eq!(deductions.mines, vec![0, 1]);
+        assert_eq!(deductions.safe, vec![6, 7]);
+    }
+
+    #[test]
+    fn mine_count_rule() {
+        // the 1 holds the only mine, the tiles out of its reach are safe
+        let deductions = deduce_layout("*o...\n");
+        assert_eq!(deductions.mines, vec![]);
+        assert_eq!(deductions.safe, vec![3, 4]);
+
+        // the 1 holds one of three mines, the two others fill the rest
+        let deductions = deduce_layout("*o.**\n");
+        assert_eq!(deductions.mines, vec![3, 4]);
+        assert_eq!(deductions.safe, vec![]);
+    }
+
+    #[test]
+    fn known_mines_count() {
+        let minefield = Minefield::from_layout_str("*o.*\n").unwrap();
+        let deductions = deduce(&minefield, &HashSet::from([0]));
+        assert_eq!(deductions.safe, vec![2]);
+        assert_eq!(deductions.mines, vec![3]);
+    }
+
+    #[test]
+    fn wrong_flags_are_not_trusted() {
+        // the flag on 2 is wrong, taking it as a mine would call 0 safe
+        let minefield = Minefield::from_layout_str("*of..*\n").unwrap();
+        let deductions = solve(&minefield);
+        assert!(!deductions.safe.contains(&0));
+        assert!(deductions.mines.iter().all(|mine| [0, 5].contains(mine)));
+    }
+
+    #[test]
+    fn probabilities_of_a_column() {
+        // the numbers 1 2 1 1 of column 1 settle column 2, two mines are left
+        // for the four tiles of column 3
+        let minefield = Minefield::from_layout_str("oo**\noo..\noo*.\noo.*\n").unwrap();
+        let probabilities = probabilities(&minefield).unwrap();
+        assert_eq!(probabilities.len(), 8);
+        for (tile, probability) in [(8, 1.0), (9, 0.0), (10, 1.0), (11, 0.0)] {
+            assert_eq!(probabilities[&tile], probability);
+        }
+        for tile in 12..16 {
+            assert!((probabilities[&tile] - 0.5).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn probabilities_match_every_layout() {
+        for seed in 0..60 {
+            let mut minefield =
+                Minefield::with_mine_count(4, 4, 3 + seed as usize % 3, FirstClick::Safe, seed);
+            minefield.uncover_tile(seed as usize % 16);
+
+            for step in 0..3 {
+                if minefield.is_over() {
+                    break;
+                }
+                let hidden: Vec<usize> = (0..16).filter(|i| minefield.tile_is_hidden(*i)).collect();
+                let expected = brute_force(&minefield, &hidden);
+                let probabilities = probabilities(&minefield).unwrap();
+                assert_eq!(probabilities.len(), hidden.len());
+                for (tile, probability) in hidden.iter().zip(expected.iter()) {
+                    assert!(
+                        (probabilities[tile] - probability).abs() < 1e-9,
+                        "seed {seed}, tile {tile}: {} instead of {probability}",
+                        probabilities[tile]
+                    );
+                }
+
+                let safe: Vec<&usize> = hidden
+                    .iter()
+                    .filter(|i| minefield.mines_on_tile(**i) == 0)
+                    .collect();
+                if safe.is_empty() {
+                    break;
+                }
+                minefield.uncover_tile(*safe[(seed as usize + step) % safe.len()]);
+            }
+        }
+    }
+
+    /// Chance of each hidden tile to be a mine over every layout the numbers allow.
+    fn brute_force(minefield: &Minefield, hidden: &[usize]) -> Vec<f64> {
+        let numbers: Vec<(usize, i32)> = minefield
+            .existing_tiles()
+            .filter(|i| !minefield.tile_is_hidden(*i))
+            .map(|i| match minefield.get_tile_content(i) {
+                TileContent::Danger(danger_level) => (i, danger_level),
+                content => panic!("uncovered {content:?}"),
+            })
+            .collect();
+
+        let mut mine_layouts = vec![0.0; hidden.len()];
+        let mut layouts = 0.0;
+        for bits in 0u32..1 << hidden.len() {
+            if bits.count_ones() as usize != minefield.mine_count() {
+                continue;
+            }
+            let is_mine = |tile: usize| {
+                hidden
+                    .iter()
+                    .position(|hidden_tile| *hidden_tile == tile)
+                    .is_some_and(|h| bits & (1 << h) != 0)
+            };
+            let fits = numbers.iter().all(|(i, danger_level)| {
+                let mines = minefield
+                    .get_neighbours(*i)
+                    .iter()
+                    .filter(|(x, y)| is_mine(minefield.indices_to_tile(*x, *y)))
+                    .count();
+                mines as i32 == *danger_level
+            });
+            if fits {
+                layouts += 1.0;
+                for (h, mine_layout) in mine_layouts.iter_mut().enumerate() {
+                    if bits & (1 << h) != 0 {
+                        *mine_layout += 1.0;
+                    }
+                }
+            }
+        }
+        mine_layouts.iter().map(|mines| mines / layouts).collect()
+    }
+
+    #[test]
+    fn counting_helpers() {
+        assert_eq!(
+            convolve(&[1.0, 1.0], &[1.0, 2.0, 1.0]),
+            vec![1.0, 3.0, 3.0, 1.0]
+        );
+        assert!((ln_choose(10, 3) - 120f64.ln()).abs() < 1e-9);
+        assert_eq!(ln_choose(5, 0), 0.0);
+        assert_eq!(ln_choose(5, 6), f64::NEG_INFINITY);
+        assert_eq!(ln_choose(5, -1), f64::NEG_INFINITY);
+    }
+}
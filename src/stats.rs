@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::minefield::{Clicks, Minefield};
+
+/// How efficiently a board was played, comparable between boards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub elapsed: Duration,
+    pub clicks: Clicks,
+    /// Minimum number of clicks needed to clear the board, 0 until mines are placed.
+    pub three_bv: usize,
+    /// Part of the 3BV already done: revealed openings and isolated numbers.
+    pub three_bv_solved: usize,
+}
+
+impl Stats {
+    pub fn new(minefield: &Minefield) -> Stats {
+        let (three_bv, three_bv_solved) = three_bv(minefield);
+        Stats {
+            elapsed: minefield.elapsed(),
+            clicks: minefield.clicks(),
+            three_bv,
+            three_bv_solved,
+        }
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.three_bv_solved as f64 / seconds
+    }
+
+    /// Index of efficiency: 3BV per click, 1 or more is a perfect game.
+    pub fn ioe(&self) -> f64 {
+        if self.clicks.total() == 0 {
+            return 0.0;
+        }
+        self.three_bv_solved as f64 / self.clicks.total() as f64
+    }
+
+    /// Share of the board cleared, mostly useful on a loss.
+    pub fn completion(&self) -> f64 {
+        if self.three_bv == 0 {
+            return 0.0;
+        }
+        self.three_bv_solved as f64 / self.three_bv as f64
+    }
+}
+
+/// Counts the openings and the numbers that no opening reveals, and how many
+/// of those are already uncovered.
+fn three_bv(minefield: &Minefield) -> (usize, usize) {
+    if !minefield.mines_placed() {
+        return (0, 0);
+    }
+
+    let neighbours = |i: usize| -> Vec<usize> {
+        minefield
+            .get_neighbours(i)
+            .iter()
+            .map(|(x, y)| minefield.indices_to_tile(*x, *y))
+            .collect()
+    };
+
+    let mut three_bv = 0;
+    let mut solved = 0;
+    let mut in_opening = HashSet::new();
+
+    // each opening is one click: flood its zeros along with the numbers bordering them
+    for i in minefield.existing_tiles() {
+        if !minefield.opens_area(i) || in_opening.contains(&i) {
+            continue;
+        }
+
+        three_bv += 1;
+        let mut opening_revealed = false;
+        let mut to_visit = vec![i];
+        in_opening.insert(i);
+        while let Some(tile_number) = to_visit.pop() {
+            if !minefield.opens_area(tile_number) {
+                continue;
+            }
+            // a number on the border can be uncovered on its own, the zeros
+            // only come with the whole opening
+            opening_revealed |= !minefield.tile_is_hidden(tile_number);
+            for neighbour in neighbours(tile_number) {
+                if in_opening.insert(neighbour) {
+                    to_visit.push(neighbour);
+                }
+            }
+        }
+        if opening_revealed {
+            solved += 1;
+        }
+    }
+
+    // every other safe tile takes its own click
+    for i in minefield.existing_tiles() {
+        if in_opening.contains(&i) || minefield.mines_on_tile(i) > 0 {
+            continue;
+        }
+        three_bv += 1;
+        if !minefield.tile_is_hidden(i) {
+            solved += 1;
+        }
+    }
+
+    (three_bv, solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn three_bv_of(layout: &str) -> (usize, usize) {
+        three_bv(&Minefield::from_layout_str(layout).unwrap())
+    }
+
+    #[test]
+    fn openings_count_once() {
+        // two zeros on each side of the mine, each opening its number
+        assert_eq!(three_bv_of("..*..\n"), (2, 0));
+        // two openings sharing the numbers between them
+        assert_eq!(three_bv_of("*...\n....\n...*\n"), (2, 0));
+    }
+
+    #[test]
+    fn isolated_numbers_count_each() {
+        assert_eq!(three_bv_of("*.*\n"), (1, 0));
+        assert_eq!(three_bv_of("...\n.*.\n...\n"), (8, 0));
+    }
+
+    #[test]
+    fn solved_part() {
+        // the isolated 2 and the opening on the right, both uncovered
+        assert_eq!(three_bv_of("*.*..\n"), (2, 0));
+        assert_eq!(three_bv_of("*o*..\n"), (2, 1));
+        assert_eq!(three_bv_of("*o*.o\n"), (2, 2));
+        // uncovering a number bordering an opening does not solve it
+        assert_eq!(three_bv_of("*...\n....\nooo*\n"), (2, 1));
+        assert_eq!(three_bv_of("*o..\n....\n...*\n"), (2, 0));
+    }
+}
@@ -0,0 +1,181 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::ttf;
+
+use std::error::Error;
+
+use crate::minefield::{GameState, Minefield};
+
+/// Remaining mines, the game clock and the reset button, drawn above the board.
+pub struct StatusBarRenderer {
+    area: Rect,
+    counter_digits: Vec<Rect>,
+    timer_digits: Vec<Rect>,
+    reset_button: Rect,
+    /// Between the counter and the reset button, `None` when the bar is too narrow.
+    record_notice: Option<Rect>,
+    /// Whether the won game on screen beat the best time of its board.
+    new_record: bool,
+    textures: StatusBarTextures,
+}
+
+impl StatusBarRenderer {
+    /// Height of the bar for a given tile size, including the gap below it.
+    pub fn height(tile_size: (usize, usize), tile_gap: usize) -> usize {
+        tile_size.1 + 4 * tile_gap
+    }
+
+    pub fn new<C>(
+        texture_creator: &TextureCreator<C>,
+        ttf_context: &ttf::Sdl2TtfContext,
+        area: Rect,
+    ) -> Result<StatusBarRenderer, Box<dyn Error>> {
+        let digit_width = area.height() * 3 / 5;
+        let digit_rect = |i: u32, x: i32| {
+            Rect::new(
+                x + (i * digit_width) as i32,
+                area.y(),
+                digit_width,
+                area.height(),
+            )
+        };
+
+        let counter_digits = (0..3).map(|i| digit_rect(i, area.x())).collect();
+        let timer_x = area.right() - 3 * digit_width as i32;
+        let timer_digits = (0..3).map(|i| digit_rect(i, timer_x)).collect();
+        let reset_button = Rect::from_center(area.center(), area.height(), area.height());
+        let notice_x = area.x() + 4 * digit_width as i32;
+        let notice_width = reset_button.left() - digit_width as i32 - notice_x;
+        let record_notice = (notice_width > 0).then(|| {
+            Rect::new(
+                notice_x,
+                area.y() + area.height() as i32 / 4,
+                notice_width as u32,
+                area.height() / 2,
+            )
+        });
+
+        let mut font =
+            ttf_context.load_font("assets/fonts/lm-mono-font/Lmmono12Regular-K7qoZ.otf", 128)?;
+        font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+        let textures = StatusBarTextures::new(font, texture_creator)?;
+
+        Ok(StatusBarRenderer {
+            area,
+            counter_digits,
+            timer_digits,
+            reset_button,
+            record_notice,
+            new_record: false,
+            textures,
+        })
+    }
+
+    pub fn draw<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        minefield: &Minefield,
+    ) -> Result<(), Box<dyn Error>> {
+        let background = match minefield.state() {
+            GameState::Won => Color::RGB(40, 90, 40),
+            GameState::Lost => Color::RGB(110, 30, 30),
+            _ => Color::RGB(30, 30, 30),
+        };
+        canvas.set_draw_color(background);
+        canvas.fill_rect(self.area)?;
+
+        let remaining_mines = minefield.mine_count() as i64 - minefield.mine_flag_counter() as i64;
+        self.draw_number(canvas, &self.counter_digits, remaining_mines)?;
+        self.draw_number(
+            canvas,
+            &self.timer_digits,
+            minefield.elapsed().as_secs() as i64,
+        )?;
+
+        canvas.set_draw_color(Color::RGB(240, 240, 240));
+        canvas.fill_rect(self.reset_button)?;
+        let face = match minefield.state() {
+            GameState::Ready | GameState::Playing => &self.textures.face_playing,
+            GameState::Won => &self.textures.face_won,
+            GameState::Lost => &self.textures.face_lost,
+        };
+        canvas.copy(face, None, Some(self.reset_button))?;
+
+        if let (true, GameState::Won, Some(zone)) =
+            (self.new_record, minefield.state(), self.record_notice)
+        {
+            canvas.copy(&self.textures.new_record, None, Some(zone))?;
+        }
+
+        Ok(())
+    }
+
+    /// Shows or hides the new record notice, only drawn on won games.
+    pub fn set_new_record(&mut self, new_record: bool) {
+        self.new_record = new_record;
+    }
+
+    /// Draws a number on three digits, clamped to what fits.
+    fn draw_number<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        digit_zones: &[Rect],
+        number: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        let text = format!("{:03}", number.clamp(-99, 999));
+        for (c, draw_zone) in text.chars().zip(digit_zones.iter()) {
+            let texture = match c.to_digit(10) {
+                Some(digit) => &self.textures.digits[digit as usize],
+                None => &self.textures.minus,
+            };
+            canvas.copy(texture, None, Some(*draw_zone))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_reset_button(&self, point: Point) -> bool {
+        self.reset_button.contains_point(point)
+    }
+}
+
+struct StatusBarTextures {
+    digits: Vec<Texture>,
+    minus: Texture,
+    face_playing: Texture,
+    face_won: Texture,
+    face_lost: Texture,
+    new_record: Texture,
+}
+
+impl StatusBarTextures {
+    pub fn new<C>(
+        font: ttf::Font,
+        texture_creator: &TextureCreator<C>,
+    ) -> Result<StatusBarTextures, Box<dyn Error>> {
+        let render = |text: &str, color: Color| -> Result<Texture, String> {
+            texture_creator
+                .create_texture_from_surface(
+                    font.render(text)
+                        .blended(color)
+                        .map_err(|e| e.to_string())?,
+                )
+                .map_err(|e| e.to_string())
+        };
+
+        let counter_color = Color::RGB(222, 13, 13);
+        let digits = (0..10)
+            .map(|digit| render(&digit.to_string(), counter_color))
+            .collect::<Result<Vec<Texture>, String>>()?;
+
+        Ok(StatusBarTextures {
+            digits,
+            minus: render("-", counter_color)?,
+            face_playing: render(":)", Color::RGB(50, 50, 50))?,
+            face_won: render("B)", Color::RGB(74, 200, 37))?,
+            face_lost: render(":(", Color::RGB(202, 0, 0))?,
+            new_record: render("New record!", Color::RGB(230, 190, 40))?,
+        })
+    }
+}
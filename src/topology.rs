@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How the edges of the board are glued together.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+    /// Bounded rectangle, the classic board.
+    Plane,
+    /// Left and right edges are joined.
+    Cylinder,
+    /// Left and right edges are joined, and so are top and bottom.
+    Torus,
+    /// Left and right edges are joined upside down.
+    Mobius,
+    /// Left and right edges are joined upside down, top and bottom are joined.
+    Klein,
+}
+
+impl Topology {
+    /// Whether leaving through the left or right edge comes back on the other side.
+    pub fn wraps_cols(&self) -> bool {
+        !matches!(self, Topology::Plane)
+    }
+
+    /// Whether leaving through the top or bottom edge comes back on the other side.
+    pub fn wraps_rows(&self) -> bool {
+        matches!(self, Topology::Torus | Topology::Klein)
+    }
+
+    /// Whether crossing the left or right edge turns the board upside down.
+    pub fn flips_rows(&self) -> bool {
+        matches!(self, Topology::Mobius | Topology::Klein)
+    }
+
+    /// Row of the other side edge that `row` is glued to.
+    pub fn glued_row(&self, rows: usize, row: usize) -> usize {
+        if self.flips_rows() {
+            rows - 1 - row
+        } else {
+            row
+        }
+    }
+
+    /// Brings a position that may be past an edge back on the board, if the
+    /// edge wraps.
+    pub fn wrap(&self, rows: usize, cols: usize, row: i64, col: i64) -> Option<(usize, usize)> {
+        let (rows_i, cols_i) = (rows as i64, cols as i64);
+        let mut row = row;
+        let mut col = col;
+
+        if !(0..cols_i).contains(&col) {
+            if !self.wraps_cols() {
+                return None;
+            }
+            // every crossing of a flipping edge turns the board over once more
+            let crossings = col.div_euclid(cols_i);
+            col = col.rem_euclid(cols_i);
+            if self.flips_rows() && crossings % 2 != 0 {
+                row = rows_i - 1 - row;
+            }
+        }
+
+        if !(0..rows_i).contains(&row) {
+            if !self.wraps_rows() {
+                return None;
+            }
+            row = row.rem_euclid(rows_i);
+        }
+
+        Some((row as usize, col as usize))
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Topology, String> {
+        match s {
+            "plane" => Ok(Topology::Plane),
+            "cylinder" => Ok(Topology::Cylinder),
+            "torus" => Ok(Topology::Torus),
+            "mobius" => Ok(Topology::Mobius),
+            "klein" => Ok(Topology::Klein),
+            _ => Err(format!("unknown topology '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Topology::Plane => "plane",
+            Topology::Cylinder => "cylinder",
+            Topology::Torus => "torus",
+            Topology::Mobius => "mobius",
+            Topology::Klein => "klein",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Touching neighbours of a tile on a 4 x 5 board.
+    fn neighbours(topology: Topology, row: i64, col: i64) -> BTreeSet<(usize, usize)> {
+        let mut neighbours = BTreeSet::new();
+        for d_row in -1..=1 {
+            for d_col in -1..=1 {
+                if (d_row, d_col) == (0, 0) {
+                    continue;
+                }
+                neighbours.extend(topology.wrap(4, 5, row + d_row, col + d_col));
+            }
+        }
+        neighbours
+    }
+
+    fn set(tiles: &[(usize, usize)]) -> BTreeSet<(usize, usize)> {
+        tiles.iter().copied().collect()
+    }
+
+    #[test]
+    fn corner() {
+        let inside = [(0, 1), (1, 0), (1, 1)];
+        assert_eq!(neighbours(Topology::Plane, 0, 0), set(&inside));
+        assert_eq!(
+            neighbours(Topology::Cylinder, 0, 0),
+            set(&[inside.as_slice(), &[(0, 4), (1, 4)]].concat())
+        );
+        assert_eq!(
+            neighbours(Topology::Torus, 0, 0),
+            set(&[inside.as_slice(), &[(0, 4), (1, 4), (3, 4), (3, 0), (3, 1)]].concat())
+        );
+        // across the side the rows are upside down, the top row has nothing above
+        assert_eq!(
+            neighbours(Topology::Mobius, 0, 0),
+            set(&[inside.as_slice(), &[(3, 4), (2, 4)]].concat())
+        );
+        // the row above the flipped top row is the top row again
+        assert_eq!(
+            neighbours(Topology::Klein, 0, 0),
+            set(&[inside.as_slice(), &[(3, 4), (2, 4), (0, 4), (3, 0), (3, 1)]].concat())
+        );
+    }
+
+    #[test]
+    fn side_edge() {
+        let inside = [(0, 3), (1, 3), (2, 3), (0, 4), (2, 4)];
+        assert_eq!(neighbours(Topology::Plane, 1, 4), set(&inside));
+        let straight = set(&[inside.as_slice(), &[(0, 0), (1, 0), (2, 0)]].concat());
+        assert_eq!(neighbours(Topology::Cylinder, 1, 4), straight);
+        assert_eq!(neighbours(Topology::Torus, 1, 4), straight);
+        let flipped = set(&[inside.as_slice(), &[(3, 0), (2, 0), (1, 0)]].concat());
+        assert_eq!(neighbours(Topology::Mobius, 1, 4), flipped);
+        assert_eq!(neighbours(Topology::Klein, 1, 4), flipped);
+    }
+}